@@ -11,6 +11,9 @@ EMPTY="$ROOT/empty.txt"
 FOX="$ROOT/fox.txt"
 SPIDERS="$ROOT/spiders.txt"
 BUSTLE="$ROOT/the-bustle.txt"
+NONPRINTING="$ROOT/nonprinting.txt"
+BLANKS="$ROOT/blanks.txt"
+BINARY="$ROOT/fox.txt.gz"
 ALL="$EMPTY $FOX $SPIDERS $BUSTLE"
 
 for FILE in $ALL; do
@@ -24,6 +27,22 @@ cat    $ALL > $OUT_DIR/all.out
 cat -n $ALL > $OUT_DIR/all.n.out
 cat -b $ALL > $OUT_DIR/all.b.out
 
+BASENAME=$(basename "$NONPRINTING")
+for FLAG in v E T A; do
+    cat -$FLAG $NONPRINTING > ${OUT_DIR}/${BASENAME}.${FLAG}.out
+done
+cat -nA $NONPRINTING > ${OUT_DIR}/${BASENAME}.nA.out
+
+BASENAME=$(basename "$BLANKS")
+cat -s  $BLANKS > ${OUT_DIR}/${BASENAME}.s.out
+cat -sn $BLANKS > ${OUT_DIR}/${BASENAME}.sn.out
+cat -sb $BLANKS > ${OUT_DIR}/${BASENAME}.sb.out
+cat -sn $BLANKS $BUSTLE $BLANKS > ${OUT_DIR}/all-blanks.sn.out
+
+cat $NONPRINTING > ${OUT_DIR}/$(basename $NONPRINTING).out
+cat $BINARY > ${OUT_DIR}/$(basename $BINARY).out
+cat $FOX $BINARY $NONPRINTING $BUSTLE > ${OUT_DIR}/all-binary.out
+
 cat    < $BUSTLE > $OUT_DIR/$(basename $BUSTLE).stdin.out
 cat -n < $BUSTLE > $OUT_DIR/$(basename $BUSTLE).n.stdin.out
 cat -b < $BUSTLE > $OUT_DIR/$(basename $BUSTLE).b.stdin.out
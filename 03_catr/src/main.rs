@@ -1,7 +1,8 @@
 use anyhow::Result;
 use clap::Parser;
+use cliutil::{open, Reporter};
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, Write};
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
@@ -22,49 +23,180 @@ struct Args {
     /// Number non-blank lines
     #[arg(short('b'), long("number-nonblank"))]
     number_nonblank_lines: bool,
+
+    /// Suppress repeated empty output lines
+    #[arg(short('s'), long("squeeze-blank"))]
+    squeeze_blank: bool,
+
+    /// Use ^ and M- notation, except for LFD and TAB
+    #[arg(short('v'), long("show-nonprinting"))]
+    show_nonprinting: bool,
+
+    /// Display $ at end of each line
+    #[arg(short('E'), long("show-ends"))]
+    show_ends: bool,
+
+    /// Display TAB characters as ^I
+    #[arg(short('T'), long("show-tabs"))]
+    show_tabs: bool,
+
+    /// Equivalent to -vET
+    #[arg(short('A'), long("show-all"))]
+    show_all: bool,
 }
 
 // --------------------------------------------------
 fn main() {
-    if let Err(e) = run(Args::parse()) {
-        eprintln!("{e}");
-        std::process::exit(1);
+    let mut reporter = Reporter::new(env!("CARGO_BIN_NAME"));
+    if let Err(e) = run(Args::parse(), &mut reporter) {
+        reporter.report(e);
     }
+    std::process::exit(reporter.exit_code());
 }
 
 // --------------------------------------------------
-fn run(args: Args) -> Result<()> {
-    for filename in args.files {
-        match open(&filename) {
-            Err(e) => eprintln!("{filename}: {e}"),
-            Ok(file) => {
-                let mut prev_num = 0;
-                for (line_num, line_result) in file.lines().enumerate() {
-                    let line = line_result?;
+fn run(mut args: Args, reporter: &mut Reporter) -> Result<()> {
+    if args.show_all {
+        args.show_nonprinting = true;
+        args.show_ends = true;
+        args.show_tabs = true;
+    }
+
+    if !(args.number_lines
+        || args.number_nonblank_lines
+        || args.squeeze_blank
+        || args.show_nonprinting
+        || args.show_ends
+        || args.show_tabs)
+    {
+        return copy_files(&args.files, reporter);
+    }
+
+    let mut stdout = io::stdout().lock();
+    let mut line_num = 0;
+    let mut prev_num = 0;
+    let mut prev_blank = false;
+    let mut line_start = true;
+    let mut line = Vec::new();
+
+    for filename in &args.files {
+        match open(filename) {
+            Err(e) => reporter.report_file(filename, e),
+            Ok(mut file) => loop {
+                line.clear();
+                match file.read_until(b'\n', &mut line) {
+                    Err(e) => {
+                        reporter.report_file(filename, e);
+                        break;
+                    }
+                    Ok(0) => break,
+                    Ok(_) => {}
+                }
+
+                // A file that ends without a newline runs into the next one
+                let blank = line_start && line == b"\n";
+                if args.squeeze_blank && blank && prev_blank {
+                    continue;
+                }
+                prev_blank = blank;
+
+                if line_start {
                     if args.number_lines {
-                        println!("{:6}\t{line}", line_num + 1);
-                    } else if args.number_nonblank_lines {
-                        if line.is_empty() {
-                            println!();
-                        } else {
-                            prev_num += 1;
-                            println!("{prev_num:6}\t{line}");
-                        }
-                    } else {
-                        println!("{line}");
+                        line_num += 1;
+                        write!(stdout, "{line_num:6}\t")?;
+                    } else if args.number_nonblank_lines && !blank {
+                        prev_num += 1;
+                        write!(stdout, "{prev_num:6}\t")?;
                     }
                 }
+                write_line(&mut stdout, &line, &args)?;
+                line_start = line.ends_with(b"\n");
+            },
+        }
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+// Copy each file to STDOUT unchanged. Passing the concrete `File` and
+// `StdinLock` types lets `io::copy` use `copy_file_range`/`splice` on Linux.
+fn copy_files(files: &[String], reporter: &mut Reporter) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    for filename in files {
+        let copied = match filename.as_str() {
+            "-" => io::copy(&mut io::stdin().lock(), &mut stdout),
+            _ => match File::open(filename) {
+                Err(e) => {
+                    reporter.report_file(filename, e);
+                    continue;
+                }
+                Ok(mut file) => io::copy(&mut file, &mut stdout),
+            },
+        };
+        if let Err(e) = copied {
+            reporter.report_file(filename, e);
+        }
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+fn write_line(
+    out: &mut impl Write,
+    line: &[u8],
+    args: &Args,
+) -> io::Result<()> {
+    let (content, newline) = match line.strip_suffix(b"\n") {
+        Some(content) => (content, true),
+        None => (line, false),
+    };
+
+    // GNU cat shows a CRLF ending as "^M$" even without -v
+    let (content, carriage_return) =
+        match (args.show_ends && newline, content.strip_suffix(b"\r")) {
+            (true, Some(content)) => (content, true),
+            _ => (content, false),
+        };
+
+    if args.show_nonprinting || args.show_tabs {
+        for &byte in content {
+            if byte == b'\t' {
+                out.write_all(if args.show_tabs { b"^I" } else { b"\t" })?;
+            } else if args.show_nonprinting {
+                write_nonprinting(out, byte)?;
+            } else {
+                out.write_all(&[byte])?;
             }
         }
+    } else {
+        out.write_all(content)?;
+    }
+
+    if carriage_return {
+        out.write_all(b"^M")?;
+    }
+
+    if newline {
+        out.write_all(if args.show_ends { b"$\n" } else { b"\n" })?;
     }
 
     Ok(())
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> Result<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+fn write_nonprinting(out: &mut impl Write, byte: u8) -> io::Result<()> {
+    let byte = if byte >= 128 {
+        out.write_all(b"M-")?;
+        byte - 128
+    } else {
+        byte
+    };
+
+    match byte {
+        0..=31 => out.write_all(&[b'^', byte + 64]),
+        127 => out.write_all(b"^?"),
+        _ => out.write_all(&[byte]),
     }
 }
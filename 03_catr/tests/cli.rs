@@ -10,6 +10,9 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const SPIDERS: &str = "tests/inputs/spiders.txt";
 const BUSTLE: &str = "tests/inputs/the-bustle.txt";
+const NONPRINTING: &str = "tests/inputs/nonprinting.txt";
+const BLANKS: &str = "tests/inputs/blanks.txt";
+const BINARY: &str = "tests/inputs/fox.txt.gz";
 
 // --------------------------------------------------
 #[test]
@@ -42,11 +45,66 @@ fn gen_bad_file() -> String {
 #[test]
 fn skips_bad_file() -> Result<()> {
     let bad = gen_bad_file();
-    let expected = format!("{bad}: .* [(]os error 2[)]");
+    let expected = format!("{PRG}: {bad}: .* [(]os error 2[)]");
     Command::cargo_bin(PRG)?
-        .arg(&bad)
+        .args([FOX, &bad, SPIDERS])
         .assert()
-        .success()
+        .failure()
+        .code(1)
+        .stdout(
+            predicate::str::contains("fox")
+                .and(predicate::str::contains("spiders")),
+        )
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_bad_file_n() -> Result<()> {
+    let bad = gen_bad_file();
+    let expected = format!("{PRG}: {bad}: .* [(]os error 2[)]");
+    Command::cargo_bin(PRG)?
+        .args(["-n", &bad, FOX])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout("     1\tThe quick brown fox jumps over the lazy dog.\n")
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_dir() -> Result<()> {
+    let expected = format!("{PRG}: tests/inputs: .* [(]os error 21[)]");
+    Command::cargo_bin(PRG)?
+        .args([FOX, "tests/inputs", FOX])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout(format!(
+            "{}{}",
+            fs::read_to_string(FOX)?,
+            fs::read_to_string(FOX)?
+        ))
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn skips_dir_n() -> Result<()> {
+    let expected = format!("{PRG}: tests/inputs: .* [(]os error 21[)]");
+    Command::cargo_bin(PRG)?
+        .args(["-n", FOX, "tests/inputs", FOX])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout(
+            "     1\tThe quick brown fox jumps over the lazy dog.\n     \
+            2\tThe quick brown fox jumps over the lazy dog.\n",
+        )
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -63,6 +121,16 @@ fn run(args: &[&str], expected_file: &str) -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+fn run_bytes(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read(expected_file)?;
+    let output = Command::cargo_bin(PRG)?.args(args).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run_stdin(
     input_file: &str,
@@ -201,3 +269,104 @@ fn all_n() -> Result<()> {
 fn all_b() -> Result<()> {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_v() -> Result<()> {
+    run(&["-v", NONPRINTING], "tests/expected/nonprinting.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_e() -> Result<()> {
+    run_bytes(
+        &["--show-ends", NONPRINTING],
+        "tests/expected/nonprinting.txt.E.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_t() -> Result<()> {
+    run_bytes(&["-T", NONPRINTING], "tests/expected/nonprinting.txt.T.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_a() -> Result<()> {
+    run(&["-A", NONPRINTING], "tests/expected/nonprinting.txt.A.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting_n_a() -> Result<()> {
+    run(
+        &["-n", "-A", NONPRINTING],
+        "tests/expected/nonprinting.txt.nA.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_s() -> Result<()> {
+    run(&["-s", BLANKS], "tests/expected/blanks.txt.s.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_sn() -> Result<()> {
+    run(
+        &["--squeeze-blank", "-n", BLANKS],
+        "tests/expected/blanks.txt.sn.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn blanks_sb() -> Result<()> {
+    run(&["-s", "-b", BLANKS], "tests/expected/blanks.txt.sb.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn all_blanks_sn() -> Result<()> {
+    run(
+        &["-s", "-n", BLANKS, BUSTLE, BLANKS],
+        "tests/expected/all-blanks.sn.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn nonprinting() -> Result<()> {
+    run_bytes(&[NONPRINTING], "tests/expected/nonprinting.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary() -> Result<()> {
+    run_bytes(&[BINARY], "tests/expected/fox.txt.gz.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_stdin() -> Result<()> {
+    let input = fs::read(BINARY)?;
+    let expected = fs::read("tests/expected/fox.txt.gz.out")?;
+    let output = Command::cargo_bin(PRG)?
+        .write_stdin(input)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn all_binary() -> Result<()> {
+    run_bytes(
+        &[FOX, BINARY, NONPRINTING, BUSTLE],
+        "tests/expected/all-binary.out",
+    )
+}
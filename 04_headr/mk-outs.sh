@@ -13,6 +13,8 @@ for FILE in $INPUTS/*.txt; do
     head -c 1 $FILE > ${OUT_DIR}/${BASENAME}.c1.out
     head -c 2 $FILE > ${OUT_DIR}/${BASENAME}.c2.out
     head -c 4 $FILE > ${OUT_DIR}/${BASENAME}.c4.out
+    head -n -2 $FILE > ${OUT_DIR}/${BASENAME}.n-2.out
+    head -c -4 $FILE > ${OUT_DIR}/${BASENAME}.c-4.out
 done
 
 ALL="$INPUTS/empty.txt $INPUTS/one.txt $INPUTS/two.txt $INPUTS/three.txt \
@@ -23,3 +25,12 @@ head -n 4 $ALL > $OUT_DIR/all.n4.out
 head -c 1 $ALL > $OUT_DIR/all.c1.out
 head -c 2 $ALL > $OUT_DIR/all.c2.out
 head -c 4 $ALL > $OUT_DIR/all.c4.out
+head -n -2 $ALL > $OUT_DIR/all.n-2.out
+head -c -4 $ALL > $OUT_DIR/all.c-4.out
+
+head -q -n 2 $ALL > $OUT_DIR/all.q.n2.out
+head -v $INPUTS/one.txt > $OUT_DIR/one.txt.v.out
+
+NUL="$INPUTS/twelve.nul"
+head -z -n 2 $NUL > $OUT_DIR/twelve.nul.z.n2.out
+head -z -n -2 $NUL > $OUT_DIR/twelve.nul.z.n-2.out
@@ -1,7 +1,9 @@
+use crate::Count::*;
 use anyhow::Result;
 use clap::Parser;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader, Read};
+use cliutil::{open, Reporter};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, Read, Write};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
@@ -11,67 +13,91 @@ struct Args {
     #[arg(default_value = "-", value_name = "FILE")]
     files: Vec<String>,
 
-    /// Number of lines
+    /// Number of lines; with a leading "-", all but the last LINES
     #[arg(
         short('n'),
         long,
         default_value = "10",
         value_name = "LINES",
-        value_parser = clap::value_parser!(u64).range(1..)
+        allow_hyphen_values = true,
+        value_parser = parse_count
     )]
-    lines: u64,
+    lines: Count,
 
-    /// Number of bytes
+    /// Number of bytes; with a leading "-", all but the last BYTES
     #[arg(
         short('c'),
         long,
         value_name = "BYTES",
         conflicts_with("lines"),
-        value_parser = clap::value_parser!(u64).range(1..)
+        allow_hyphen_values = true,
+        value_parser = parse_count
     )]
-    bytes: Option<u64>,
+    bytes: Option<Count>,
+
+    /// Never print headers giving file names
+    #[arg(short, long, visible_alias("silent"), overrides_with("verbose"))]
+    quiet: bool,
+
+    /// Always print headers giving file names
+    #[arg(short, long, overrides_with("quiet"))]
+    verbose: bool,
+
+    /// Line delimiter is NUL, not newline
+    #[arg(short, long("zero-terminated"))]
+    zero_terminated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Count {
+    First(u64),
+    AllBut(u64),
 }
 
 // --------------------------------------------------
 fn main() {
-    if let Err(e) = run(Args::parse()) {
-        eprintln!("{e}");
-        std::process::exit(1);
+    let mut reporter = Reporter::new(env!("CARGO_BIN_NAME"));
+    if let Err(e) = run(Args::parse(), &mut reporter) {
+        reporter.report(e);
     }
+    std::process::exit(reporter.exit_code());
 }
 
 // --------------------------------------------------
-fn run(args: Args) -> Result<()> {
+fn run(args: Args, reporter: &mut Reporter) -> Result<()> {
     let num_files = args.files.len();
+    let show_headers = !args.quiet && (args.verbose || num_files > 1);
+    let delimiter = if args.zero_terminated { b'\0' } else { b'\n' };
 
     for (file_num, filename) in args.files.iter().enumerate() {
         match open(filename) {
-            Err(err) => eprintln!("{filename}: {err}"),
-            Ok(mut file) => {
-                if num_files > 1 {
+            Err(err) => reporter.report_file(filename, err),
+            Ok(file) => {
+                if show_headers {
                     println!(
                         "{}==> {filename} <==",
                         if file_num > 0 { "\n" } else { "" },
                     );
                 }
 
-                if let Some(num_bytes) = args.bytes {
-                    let mut buffer = vec![0; num_bytes as usize];
-                    let bytes_read = file.read(&mut buffer)?;
-                    print!(
-                        "{}",
-                        String::from_utf8_lossy(&buffer[..bytes_read])
-                    );
-                } else {
-                    let mut line = String::new();
-                    for _ in 0..args.lines {
-                        let bytes = file.read_line(&mut line)?;
-                        if bytes == 0 {
-                            break;
-                        }
-                        print!("{line}");
-                        line.clear();
+                match (args.bytes, args.lines) {
+                    (Some(First(num_bytes)), _) => {
+                        io::copy(
+                            &mut file.take(num_bytes),
+                            &mut io::stdout(),
+                        )?;
                     }
+                    (Some(AllBut(num_bytes)), _) => {
+                        print_all_but_bytes(file, num_bytes as usize)?
+                    }
+                    (None, First(num_lines)) => {
+                        print_lines(file, num_lines, delimiter)?
+                    }
+                    (None, AllBut(num_lines)) => print_all_but_lines(
+                        file,
+                        num_lines as usize,
+                        delimiter,
+                    )?,
                 }
             }
         }
@@ -81,9 +107,169 @@ fn run(args: Args) -> Result<()> {
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> Result<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+// Print the first `num_lines` lines, each ending with `delimiter`.
+fn print_lines(
+    mut file: impl BufRead,
+    num_lines: u64,
+    delimiter: u8,
+) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    let mut line = Vec::new();
+    for _ in 0..num_lines {
+        let bytes = file.read_until(delimiter, &mut line)?;
+        if bytes == 0 {
+            break;
+        }
+        stdout.write_all(&line)?;
+        line.clear();
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+// Print all but the last `num_lines` lines, holding back at most that
+// many lines so this works on pipes.
+fn print_all_but_lines(
+    mut file: impl BufRead,
+    num_lines: usize,
+    delimiter: u8,
+) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    let mut held: VecDeque<Vec<u8>> = VecDeque::new();
+    loop {
+        let mut line = Vec::new();
+        if file.read_until(delimiter, &mut line)? == 0 {
+            break;
+        }
+        held.push_back(line);
+        if held.len() > num_lines {
+            if let Some(line) = held.pop_front() {
+                stdout.write_all(&line)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+// Print all but the last `num_bytes` bytes, holding back at most that
+// many bytes (plus one read buffer) so this works on pipes.
+fn print_all_but_bytes(mut file: impl Read, num_bytes: usize) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    let mut held: VecDeque<u8> = VecDeque::new();
+    let mut buffer = [0; 64 * 1024];
+    loop {
+        let bytes_read = file.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        held.extend(&buffer[..bytes_read]);
+        if held.len() > num_bytes {
+            let excess = held.len() - num_bytes;
+            let (front, back) = held.as_slices();
+            let split = excess.min(front.len());
+            stdout.write_all(&front[..split])?;
+            stdout.write_all(&back[..excess - split])?;
+            held.drain(..excess);
+        }
+    }
+
+    Ok(())
+}
+
+// --------------------------------------------------
+// Parse a count with an optional leading "-" (meaning "all but") and an
+// optional size suffix such as "K", "kB", "MiB" or "b".
+fn parse_count(val: &str) -> Result<Count, String> {
+    let (all_but, num) = match val.strip_prefix('-') {
+        Some(num) => (true, num),
+        None => (false, val.strip_prefix('+').unwrap_or(val)),
+    };
+
+    let split = num.find(|c: char| !c.is_ascii_digit()).unwrap_or(num.len());
+    let (digits, suffix) = num.split_at(split);
+    let multiplier = match suffix_multiplier(suffix) {
+        Some(multiplier) => multiplier,
+        // Report an unknown suffix as a malformed number
+        None => return Err("invalid digit found in string".to_string()),
+    };
+
+    let value = digits
+        .parse::<u64>()
+        .map_err(|e| e.to_string())?
+        .checked_mul(multiplier)
+        .ok_or_else(|| "value too large".to_string())?;
+
+    Ok(if all_but { AllBut(value) } else { First(value) })
+}
+
+// --------------------------------------------------
+fn suffix_multiplier(suffix: &str) -> Option<u64> {
+    let exponent = match suffix {
+        "" => return Some(1),
+        "b" => return Some(512),
+        _ => match suffix.chars().next().map(|c| c.to_ascii_uppercase()) {
+            Some('K') => 1,
+            Some('M') => 2,
+            Some('G') => 3,
+            Some('T') => 4,
+            Some('P') => 5,
+            Some('E') => 6,
+            _ => return None,
+        },
+    };
+
+    let base: u64 = match &suffix[1..] {
+        "" | "iB" => 1024,
+        "B" => 1000,
+        _ => return None,
+    };
+
+    base.checked_pow(exponent)
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{parse_count, Count::*};
+
+    #[test]
+    fn test_parse_count() {
+        assert_eq!(parse_count("3"), Ok(First(3)));
+        assert_eq!(parse_count("+3"), Ok(First(3)));
+        assert_eq!(parse_count("0"), Ok(First(0)));
+        assert_eq!(parse_count("-3"), Ok(AllBut(3)));
+        assert_eq!(parse_count("-0"), Ok(AllBut(0)));
+        assert_eq!(parse_count("2b"), Ok(First(1024)));
+        assert_eq!(parse_count("1K"), Ok(First(1024)));
+        assert_eq!(parse_count("1k"), Ok(First(1024)));
+        assert_eq!(parse_count("1kiB"), Ok(First(1024)));
+        assert_eq!(parse_count("1KiB"), Ok(First(1024)));
+        assert_eq!(parse_count("1kB"), Ok(First(1000)));
+        assert_eq!(parse_count("1KB"), Ok(First(1000)));
+        assert_eq!(parse_count("-2M"), Ok(AllBut(2 * 1024 * 1024)));
+        assert_eq!(parse_count("1m"), Ok(First(1024 * 1024)));
+        assert_eq!(parse_count("1mB"), Ok(First(1_000_000)));
+        assert_eq!(parse_count("2g"), Ok(First(2 * 1024 * 1024 * 1024)));
+        assert_eq!(parse_count("1t"), Ok(First(1 << 40)));
+        assert_eq!(parse_count("1GiB"), Ok(First(1024 * 1024 * 1024)));
+        assert_eq!(parse_count("3GB"), Ok(First(3_000_000_000)));
+        assert_eq!(parse_count("1E"), Ok(First(1 << 60)));
+
+        assert_eq!(
+            parse_count("foo"),
+            Err("invalid digit found in string".to_string())
+        );
+        assert_eq!(
+            parse_count("1X"),
+            Err("invalid digit found in string".to_string())
+        );
+        assert_eq!(
+            parse_count("K"),
+            Err("cannot parse integer from empty string".to_string())
+        );
+        assert_eq!(parse_count("16E"), Err("value too large".to_string()));
     }
 }
@@ -12,6 +12,7 @@ const ONE: &str = "./tests/inputs/one.txt";
 const TWO: &str = "./tests/inputs/two.txt";
 const THREE: &str = "./tests/inputs/three.txt";
 const TWELVE: &str = "./tests/inputs/twelve.txt";
+const TWELVE_NUL: &str = "./tests/inputs/twelve.nul";
 
 // --------------------------------------------------
 fn random_string() -> String {
@@ -35,14 +36,14 @@ fn gen_bad_file() -> String {
 // --------------------------------------------------
 #[test]
 fn dies_bad_bytes() -> Result<()> {
-    let bad = random_string();
+    let bad = "1X2";
     let expected = format!(
         "invalid value '{bad}' for \
         '--bytes <BYTES>': invalid digit found in string"
     );
 
     Command::cargo_bin(PRG)?
-        .args(["-c", &bad, EMPTY])
+        .args(["-c", bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -53,13 +54,13 @@ fn dies_bad_bytes() -> Result<()> {
 // --------------------------------------------------
 #[test]
 fn dies_bad_lines() -> Result<()> {
-    let bad = random_string();
+    let bad = "1X2";
     let expected = format!(
         "error: invalid value '{bad}' for \
         '--lines <LINES>': invalid digit found in string"
     );
     Command::cargo_bin(PRG)?
-        .args(["-n", &bad, EMPTY])
+        .args(["-n", bad, EMPTY])
         .assert()
         .failure()
         .stderr(predicate::str::contains(expected));
@@ -86,10 +87,13 @@ fn dies_bytes_and_lines() -> Result<()> {
 #[test]
 fn skips_bad_file() -> Result<()> {
     let bad = gen_bad_file();
-    let expected = format!("{bad}: .* [(]os error 2[)]");
+    let expected = format!("{PRG}: {bad}: .* [(]os error 2[)]");
     Command::cargo_bin(PRG)?
         .args([EMPTY, &bad, ONE])
         .assert()
+        .failure()
+        .code(1)
+        .stdout(predicate::str::contains(format!("==> {ONE} <==")))
         .stderr(predicate::str::is_match(expected)?);
 
     Ok(())
@@ -423,3 +427,146 @@ fn multiple_files_c4() -> Result<()> {
         "tests/expected/all.c4.out",
     )
 }
+
+// --------------------------------------------------
+#[test]
+fn three_n_minus2() -> Result<()> {
+    run(&[THREE, "-n", "-2"], "tests/expected/three.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn three_c_minus4() -> Result<()> {
+    run(&[THREE, "-c", "-4"], "tests/expected/three.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn twelve_n_minus2() -> Result<()> {
+    run(&[TWELVE, "-n", "-2"], "tests/expected/twelve.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn twelve_c_minus4() -> Result<()> {
+    run(&[TWELVE, "--bytes=-4"], "tests/expected/twelve.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn twelve_n_minus2_stdin() -> Result<()> {
+    run_stdin(&["-n", "-2"], TWELVE, "tests/expected/twelve.txt.n-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn twelve_c_minus4_stdin() -> Result<()> {
+    run_stdin(&["-c", "-4"], TWELVE, "tests/expected/twelve.txt.c-4.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn twelve_c_suffix() -> Result<()> {
+    run(&[TWELVE, "-c", "1K"], TWELVE)
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_n_minus2() -> Result<()> {
+    run(
+        &[EMPTY, ONE, TWO, THREE, TWELVE, "-n", "-2"],
+        "tests/expected/all.n-2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_c_minus4() -> Result<()> {
+    run(
+        &["-c", "-4", EMPTY, ONE, TWO, THREE, TWELVE],
+        "tests/expected/all.c-4.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_quiet() -> Result<()> {
+    run(
+        &["-q", "-n", "2", EMPTY, ONE, TWO, THREE, TWELVE],
+        "tests/expected/all.q.n2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_silent() -> Result<()> {
+    run(
+        &["--silent", "-n", "2", EMPTY, ONE, TWO, THREE, TWELVE],
+        "tests/expected/all.q.n2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn multiple_files_verbose_quiet() -> Result<()> {
+    run(
+        &["-v", "-q", "-n", "2", EMPTY, ONE, TWO, THREE, TWELVE],
+        "tests/expected/all.q.n2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn one_verbose() -> Result<()> {
+    run(&["--verbose", ONE], "tests/expected/one.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn one_quiet_verbose() -> Result<()> {
+    run(&["-q", "-v", ONE], "tests/expected/one.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn twelve_nul_z_n2() -> Result<()> {
+    run(
+        &["-z", "-n", "2", TWELVE_NUL],
+        "tests/expected/twelve.nul.z.n2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn twelve_nul_z_n_minus2() -> Result<()> {
+    run(
+        &["--zero-terminated", "-n", "-2", TWELVE_NUL],
+        "tests/expected/twelve.nul.z.n-2.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn one_c2_raw_bytes() -> Result<()> {
+    let expected = fs::read("tests/expected/one.txt.c2.out")?;
+    let output = Command::cargo_bin(PRG)?.args(["-c", "2", ONE]).output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_stdin_reads_all_bytes() -> Result<()> {
+    let input: Vec<u8> = (0..200_000).map(|i| (i % 256) as u8).collect();
+    let output = Command::cargo_bin(PRG)?
+        .write_stdin(input.clone())
+        .args(["-c", "150000"])
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout.len(), 150_000);
+    assert!(output.stdout == input[..150_000]);
+
+    Ok(())
+}
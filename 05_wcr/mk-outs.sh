@@ -6,6 +6,9 @@ OUT_DIR="tests/expected"
 
 [[ ! -d "$OUT_DIR" ]] && mkdir -p "$OUT_DIR"
 
+# Expected outputs come from GNU wc in a UTF-8 locale
+export LC_ALL=C.UTF-8
+
 for FILE in $FILES; do
     BASENAME=$(basename "$FILE")
     wc      $FILE > ${OUT_DIR}/${BASENAME}.out
@@ -19,9 +22,14 @@ for FILE in $FILES; do
     wc -wl  $FILE > ${OUT_DIR}/${BASENAME}.wl.out
     wc -cl  $FILE > ${OUT_DIR}/${BASENAME}.cl.out
     wc -ml  $FILE > ${OUT_DIR}/${BASENAME}.ml.out
+    wc -L   $FILE > ${OUT_DIR}/${BASENAME}.L.out
 done
 
-wc < "$ROOT/atlamal.txt" > "$OUT_DIR/atlamal.txt.stdin.out"
+cat "$ROOT/atlamal.txt" | wc > "$OUT_DIR/atlamal.txt.stdin.out"
+cat "$ROOT/atlamal.txt" | wc $ROOT/fox.txt - > "$OUT_DIR/fox-stdin.out"
+wc --files0-from=$ROOT/files0.txt > "$OUT_DIR/files0.out"
+cat $ROOT/files0.txt | wc --files0-from=- > "$OUT_DIR/files0.stdin.out"
+wc $ROOT/fox.txt $ROOT > "$OUT_DIR/fox-dir.out"
 
 wc      $FILES > $OUT_DIR/all.out
 wc -l   $FILES > $OUT_DIR/all.l.out
@@ -34,3 +42,4 @@ wc -wm  $FILES > $OUT_DIR/all.wm.out
 wc -wl  $FILES > $OUT_DIR/all.wl.out
 wc -cl  $FILES > $OUT_DIR/all.cl.out
 wc -ml  $FILES > $OUT_DIR/all.ml.out
+wc -L   $FILES > $OUT_DIR/all.L.out
@@ -1,15 +1,34 @@
-use anyhow::Result;
-use clap::Parser;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, ValueEnum};
+use cliutil::{open, Reporter};
+use serde::Serialize;
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ffi::OsStr,
+    fs,
+    io::{self, BufRead, Write},
+    os::unix::ffi::OsStrExt,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+use unicode_width::UnicodeWidthChar;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
 /// Rust version of `wc`
 struct Args {
-    /// Input file(s)
-    #[arg(value_name = "FILE", default_value = "-")]
-    files: Vec<String>,
+    /// Input file(s) [default: -]
+    #[arg(value_name = "FILE")]
+    files: Vec<PathBuf>,
+
+    /// Read NUL-terminated input file names from FILE ("-" for STDIN)
+    #[arg(long("files0-from"), value_name = "F", conflicts_with("files"))]
+    files0_from: Option<String>,
 
     /// Show line count
     #[arg(short, long)]
@@ -26,149 +45,595 @@ struct Args {
     /// Show character count
     #[arg(short('m'), long, conflicts_with("bytes"))]
     chars: bool,
+
+    /// Show maximum display width of a line
+    #[arg(short('L'), long)]
+    max_line_length: bool,
+
+    /// Output format; json writes one object per line
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_enum,
+        default_value_t = OutputFormat::Text
+    )]
+    output_format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+    Tsv,
 }
 
-#[derive(Debug, PartialEq)]
+const BUFFER_SIZE: usize = 256 * 1024;
+
+#[derive(Debug, Default, PartialEq)]
 struct FileInfo {
     num_lines: usize,
     num_words: usize,
     num_bytes: usize,
     num_chars: usize,
+    max_line_length: usize,
+}
+
+impl FileInfo {
+    fn add(&mut self, other: &FileInfo) {
+        self.num_lines += other.num_lines;
+        self.num_words += other.num_words;
+        self.num_bytes += other.num_bytes;
+        self.num_chars += other.num_chars;
+        self.max_line_length =
+            self.max_line_length.max(other.max_line_length);
+    }
+}
+
+// The counts to make; the bytes are always known
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Wanted {
+    lines: bool,
+    words: bool,
+    chars: bool,
+    max_line_length: bool,
+}
+
+impl Wanted {
+    const ALL: Wanted = Wanted {
+        lines: true,
+        words: true,
+        chars: true,
+        max_line_length: true,
+    };
+}
+
+// The machine-readable form of one file's counts or the total
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    name: Cow<'a, str>,
+    lines: usize,
+    words: usize,
+    bytes: usize,
+    chars: usize,
+    max_line_length: usize,
+}
+
+impl<'a> Record<'a> {
+    fn new(name: &'a Path, info: &FileInfo) -> Self {
+        Record {
+            name: name.to_string_lossy(),
+            lines: info.num_lines,
+            words: info.num_words,
+            bytes: info.num_bytes,
+            chars: info.num_chars,
+            max_line_length: info.max_line_length,
+        }
+    }
 }
 
 // --------------------------------------------------
 fn main() {
-    if let Err(e) = run(Args::parse()) {
-        eprintln!("{e}");
-        std::process::exit(1);
+    let mut reporter = Reporter::new(env!("CARGO_BIN_NAME"));
+    if let Err(e) = run(Args::parse(), &mut reporter) {
+        reporter.report(e);
     }
+    std::process::exit(reporter.exit_code());
 }
 
 // --------------------------------------------------
-fn run(mut args: Args) -> Result<()> {
-    if [args.words, args.bytes, args.chars, args.lines]
-        .iter()
-        .all(|v| v == &false)
+fn run(mut args: Args, reporter: &mut Reporter) -> Result<()> {
+    if [
+        args.words,
+        args.bytes,
+        args.chars,
+        args.lines,
+        args.max_line_length,
+    ]
+    .iter()
+    .all(|v| v == &false)
     {
         args.lines = true;
         args.words = true;
         args.bytes = true;
     }
 
-    let mut total_lines = 0;
-    let mut total_words = 0;
-    let mut total_bytes = 0;
-    let mut total_chars = 0;
-
-    for filename in &args.files {
-        match open(filename) {
-            Err(err) => eprintln!("{filename}: {err}"),
-            Ok(file) => {
-                if let Ok(info) = count(file) {
-                    println!(
-                        "{}{}{}{}{}",
-                        format_field(info.num_lines, args.lines),
-                        format_field(info.num_words, args.words),
-                        format_field(info.num_bytes, args.bytes),
-                        format_field(info.num_chars, args.chars),
-                        if filename == "-" {
-                            "".to_string()
-                        } else {
-                            format!(" {filename}")
-                        },
-                    );
-
-                    total_lines += info.num_lines;
-                    total_words += info.num_words;
-                    total_bytes += info.num_bytes;
-                    total_chars += info.num_chars;
+    // File names are printed unless STDIN is read by default
+    let (files, show_names) = match &args.files0_from {
+        Some(list) => (read_files0(list)?, true),
+        None if args.files.is_empty() => (vec![PathBuf::from("-")], false),
+        None => (args.files.clone(), true),
+    };
+
+    let num_columns = [
+        args.lines,
+        args.words,
+        args.bytes,
+        args.chars,
+        args.max_line_length,
+    ]
+    .iter()
+    .filter(|v| **v)
+    .count();
+
+    // Like GNU wc, the sizes of file names read from STDIN are not known
+    let width = match args.files0_from.as_deref() {
+        Some("-") => 1,
+        _ if files.len() == 1 && num_columns == 1 => 1,
+        _ => number_width(&files),
+    };
+
+    let mut csv_writer = match args.output_format {
+        OutputFormat::Csv | OutputFormat::Tsv => Some(
+            csv::WriterBuilder::new()
+                .delimiter(if args.output_format == OutputFormat::Tsv {
+                    b'\t'
+                } else {
+                    b','
+                })
+                .from_writer(io::stdout()),
+        ),
+        _ => None,
+    };
+
+    let mut print = |name: &Path, info: &FileInfo| -> Result<()> {
+        match &mut csv_writer {
+            Some(writer) => writer.serialize(Record::new(name, info))?,
+            None if args.output_format == OutputFormat::Json => println!(
+                "{}",
+                serde_json::to_string(&Record::new(name, info))?
+            ),
+            None => {
+                let fields: Vec<_> = [
+                    format_field(info.num_lines, args.lines, width),
+                    format_field(info.num_words, args.words, width),
+                    format_field(info.num_bytes, args.bytes, width),
+                    format_field(info.num_chars, args.chars, width),
+                    format_field(
+                        info.max_line_length,
+                        args.max_line_length,
+                        width,
+                    ),
+                ]
+                .into_iter()
+                .filter(|field| !field.is_empty())
+                .collect();
+                // Names are written as raw bytes, like GNU wc
+                let mut line = fields.join(" ").into_bytes();
+                if show_names {
+                    line.push(b' ');
+                    line.extend_from_slice(name.as_os_str().as_bytes());
                 }
+                line.push(b'\n');
+                io::stdout().write_all(&line)?;
+            }
+        }
+        Ok(())
+    };
+
+    // Records have every column
+    let wanted = if args.output_format == OutputFormat::Text {
+        Wanted {
+            lines: args.lines,
+            words: args.words,
+            chars: args.chars,
+            max_line_length: args.max_line_length,
+        }
+    } else {
+        Wanted::ALL
+    };
+
+    let mut total = FileInfo::default();
+    count_files(&files, wanted, |filename, result| {
+        match result {
+            Err(err) => {
+                reporter.report_file(&filename.display().to_string(), err)
+            }
+            Ok(Err(err)) => {
+                // GNU wc still prints (empty) counts for a directory
+                reporter.report_file(&filename.display().to_string(), err);
+                print(filename, &FileInfo::default())?;
+            }
+            Ok(Ok(info)) => {
+                print(filename, &info)?;
+                total.add(&info);
             }
         }
+        Ok(())
+    })?;
+
+    if files.len() > 1 || args.output_format != OutputFormat::Text {
+        print(Path::new("total"), &total)?;
     }
 
-    if args.files.len() > 1 {
-        println!(
-            "{}{}{}{} total",
-            format_field(total_lines, args.lines),
-            format_field(total_words, args.words),
-            format_field(total_bytes, args.bytes),
-            format_field(total_chars, args.chars)
-        );
+    if let Some(writer) = &mut csv_writer {
+        writer.flush()?;
     }
 
     Ok(())
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> Result<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+// The column width GNU wc uses: enough digits for the combined size of all
+// regular files, but at least 7 when any input (such as a pipe or a
+// directory) has no size known in advance.
+fn number_width(files: &[PathBuf]) -> usize {
+    let mut min_width = 1;
+    let mut total_size: u64 = 0;
+    for filename in files {
+        let path = if filename == Path::new("-") {
+            Path::new("/dev/stdin")
+        } else {
+            filename
+        };
+        if let Ok(metadata) = fs::metadata(path) {
+            if metadata.is_file() {
+                total_size += metadata.len();
+            } else {
+                min_width = 7;
+            }
+        }
     }
+
+    total_size.to_string().len().max(min_width)
 }
 
 // --------------------------------------------------
-fn format_field(value: usize, show: bool) -> String {
+// Count the files on a pool of threads, passing each result to `report`
+// in argument order as soon as it and all the results before it are ready.
+// The outer result is from opening the file, the inner from reading it.
+fn count_files(
+    files: &[PathBuf],
+    wanted: Wanted,
+    mut report: impl FnMut(&Path, Result<Result<FileInfo>>) -> Result<()>,
+) -> Result<()> {
+    let num_threads = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(files.len());
+    let next = AtomicUsize::new(0);
+    let (tx, rx) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..num_threads {
+            let tx = tx.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                let Some(filename) = files.get(i) else {
+                    break;
+                };
+                let result = open(filename).map(|file| count(file, wanted));
+                if tx.send((i, result)).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(tx);
+
+        let mut pending = HashMap::new();
+        let mut wanted = 0;
+        for (i, result) in rx {
+            pending.insert(i, result);
+            while let Some(result) = pending.remove(&wanted) {
+                report(&files[wanted], result)?;
+                wanted += 1;
+            }
+        }
+        Ok(())
+    })
+}
+
+// --------------------------------------------------
+// Read a list of NUL-terminated file names, as from `find -print0`,
+// keeping each name's bytes as they are.
+fn read_files0(list: &str) -> Result<Vec<PathBuf>> {
+    let mut file = open(list).map_err(|e| anyhow!("{list}: {e}"))?;
+    let mut files = vec![];
+    let mut name = Vec::new();
+    loop {
+        name.clear();
+        if file.read_until(b'\0', &mut name)? == 0 {
+            break;
+        }
+        let name = name.strip_suffix(b"\0").unwrap_or(&name);
+        if name.is_empty() {
+            bail!("{list}: invalid zero-length file name");
+        }
+        if list == "-" && name == b"-" {
+            bail!(
+                "when reading file names from stdin, \
+                no file name of '-' allowed"
+            );
+        }
+        files.push(PathBuf::from(OsStr::from_bytes(name)));
+    }
+
+    Ok(files)
+}
+
+// --------------------------------------------------
+fn format_field(value: usize, show: bool, width: usize) -> String {
     if show {
-        format!("{value:>8}")
+        format!("{value:>width$}")
     } else {
         "".to_string()
     }
 }
 
 // --------------------------------------------------
-fn count(mut file: impl BufRead) -> Result<FileInfo> {
-    let mut num_lines = 0;
-    let mut num_words = 0;
-    let mut num_bytes = 0;
-    let mut num_chars = 0;
-    let mut line = String::new();
-
+fn count(mut file: impl BufRead, wanted: Wanted) -> Result<FileInfo> {
+    let mut counter = Counter::new(wanted);
+    let mut buffer = vec![0; BUFFER_SIZE];
     loop {
-        let line_bytes = file.read_line(&mut line)?;
-        if line_bytes == 0 {
-            break;
+        let bytes_read = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        };
+        counter.update(&buffer[..bytes_read]);
+    }
+
+    Ok(counter.finish())
+}
+
+// --------------------------------------------------
+// Counts lines, words, bytes, characters and line width over a stream of
+// byte chunks, carrying any state that spans a chunk boundary (a partial
+// UTF-8 sequence, an open word, the width of the current line).
+#[derive(Debug)]
+struct Counter {
+    wanted: Wanted,
+    num_lines: usize,
+    num_words: usize,
+    num_bytes: usize,
+    num_chars: usize,
+    max_line_length: usize,
+    line_width: usize,
+    in_word: bool,
+    partial: Vec<u8>,
+}
+
+impl Counter {
+    fn new(wanted: Wanted) -> Self {
+        Counter {
+            wanted,
+            num_lines: 0,
+            num_words: 0,
+            num_bytes: 0,
+            num_chars: 0,
+            max_line_length: 0,
+            line_width: 0,
+            in_word: false,
+            partial: vec![],
         }
-        num_bytes += line_bytes;
-        num_lines += 1;
-        num_words += line.split_whitespace().count();
-        num_chars += line.chars().count();
-        line.clear();
     }
 
-    Ok(FileInfo {
-        num_lines,
-        num_words,
-        num_bytes,
-        num_chars,
-    })
+    fn update(&mut self, chunk: &[u8]) {
+        self.num_bytes += chunk.len();
+
+        // Only words and line widths need to look at every byte in turn,
+        // lines and characters are otherwise counted with SIMD
+        if !(self.wanted.words || self.wanted.max_line_length) {
+            if self.wanted.lines {
+                self.num_lines += bytecount::count(chunk, b'\n');
+            }
+            if self.wanted.chars {
+                self.num_chars += bytecount::num_chars(chunk);
+            }
+            return;
+        }
+
+        for &byte in chunk {
+            self.num_chars += usize::from(!is_continuation(byte));
+            if byte.is_ascii() {
+                self.flush_partial();
+                self.ascii(byte);
+            } else if is_continuation(byte) && !self.partial.is_empty() {
+                self.partial.push(byte);
+                if let Ok(s) = std::str::from_utf8(&self.partial) {
+                    let c = s.chars().next().unwrap_or_default();
+                    self.partial.clear();
+                    self.char(c);
+                } else if self.partial.len() == 4 {
+                    self.flush_partial();
+                }
+            } else {
+                self.flush_partial();
+                self.partial.push(byte);
+            }
+        }
+    }
+
+    fn finish(mut self) -> FileInfo {
+        self.flush_partial();
+        FileInfo {
+            num_lines: self.num_lines,
+            num_words: self.num_words,
+            num_bytes: self.num_bytes,
+            num_chars: self.num_chars,
+            max_line_length: self.max_line_length.max(self.line_width),
+        }
+    }
+
+    fn ascii(&mut self, byte: u8) {
+        match byte {
+            b'\n' | b'\r' | b'\x0c' => {
+                self.num_lines += usize::from(byte == b'\n');
+                self.end_word();
+                self.max_line_length =
+                    self.max_line_length.max(self.line_width);
+                self.line_width = 0;
+            }
+            b'\t' => {
+                self.end_word();
+                self.line_width += 8 - self.line_width % 8;
+            }
+            b' ' | b'\x0b' => {
+                self.end_word();
+                self.line_width += usize::from(byte == b' ');
+            }
+            _ => {
+                self.start_word();
+                self.line_width += usize::from(byte.is_ascii_graphic());
+            }
+        }
+    }
+
+    fn char(&mut self, c: char) {
+        if c.is_whitespace() {
+            self.end_word();
+        } else {
+            self.start_word();
+        }
+        self.line_width += c.width().unwrap_or(0);
+    }
+
+    // Bytes that do not form a valid UTF-8 character have no width but
+    // still belong to a word.
+    fn flush_partial(&mut self) {
+        if !self.partial.is_empty() {
+            self.partial.clear();
+            self.start_word();
+        }
+    }
+
+    fn start_word(&mut self) {
+        if !self.in_word {
+            self.num_words += 1;
+            self.in_word = true;
+        }
+    }
+
+    fn end_word(&mut self) {
+        self.in_word = false;
+    }
+}
+
+// --------------------------------------------------
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
 }
 
 // --------------------------------------------------
 #[cfg(test)]
 mod tests {
-    use super::{count, format_field, FileInfo};
+    use super::{count, format_field, FileInfo, Wanted, BUFFER_SIZE};
     use std::io::Cursor;
 
     #[test]
     fn test_count() {
         let text = "I don't want the world.\nI just want your half.\r\n";
-        let info = count(Cursor::new(text));
+        let info = count(Cursor::new(text), Wanted::ALL);
         assert!(info.is_ok());
         let expected = FileInfo {
             num_lines: 2,
             num_words: 10,
             num_chars: 48,
             num_bytes: 48,
+            max_line_length: 23,
         };
         assert_eq!(info.unwrap(), expected);
     }
 
+    #[test]
+    fn test_count_wanted() {
+        let text = "caf\u{e9} au lait\nfor two\n";
+        let only = |wanted| count(Cursor::new(text), wanted).unwrap();
+        let none = Wanted {
+            lines: false,
+            words: false,
+            chars: false,
+            max_line_length: false,
+        };
+
+        // Counts that aren't wanted are skipped
+        let info = only(Wanted {
+            lines: true,
+            ..none
+        });
+        assert_eq!(
+            (info.num_lines, info.num_words, info.num_chars),
+            (2, 0, 0)
+        );
+        let info = only(Wanted {
+            chars: true,
+            ..none
+        });
+        assert_eq!((info.num_lines, info.num_chars), (0, 21));
+        let info = only(none);
+        assert_eq!((info.num_bytes, info.num_chars), (22, 0));
+
+        // The same counts as when everything is wanted
+        let info = only(Wanted {
+            words: true,
+            ..none
+        });
+        assert_eq!(
+            (info.num_lines, info.num_words, info.num_chars),
+            (2, 5, 21)
+        );
+    }
+
     #[test]
     fn test_format_field() {
-        assert_eq!(format_field(1, false), "");
-        assert_eq!(format_field(3, true), "       3");
-        assert_eq!(format_field(10, true), "      10");
+        assert_eq!(format_field(1, false, 8), "");
+        assert_eq!(format_field(3, true, 8), "       3");
+        assert_eq!(format_field(10, true, 8), "      10");
+        assert_eq!(format_field(10, true, 1), "10");
+    }
+
+    #[test]
+    fn test_count_max_line_length() {
+        let width = |text: &str| {
+            count(Cursor::new(text), Wanted::ALL)
+                .unwrap()
+                .max_line_length
+        };
+        assert_eq!(width(""), 0);
+        assert_eq!(width("fox\n"), 3);
+        assert_eq!(width("a\tb"), 9);
+        assert_eq!(width("\u{65e5}\u{672c}\u{8a9e}"), 6);
+        assert_eq!(width("caf\u{e9}"), 4);
+        assert_eq!(width("long line\rshort\r\n"), 9);
+        assert_eq!(width("one\nthree\ntwo"), 5);
+    }
+
+    #[test]
+    fn test_count_bytes() {
+        // Invalid UTF-8 and Unicode spaces (U+00A0, U+3000)
+        let text =
+            b"caf\xc3\xa9 \xff\xfe\tnon\xc2\xa0breaking\xe3\x80\x80end\n";
+        let info = count(Cursor::new(text), Wanted::ALL).unwrap();
+        assert_eq!(info.num_lines, 1);
+        assert_eq!(info.num_words, 5);
+        assert_eq!(info.num_bytes, text.len());
+
+        // A character split across two buffer reads
+        let text = "a".repeat(BUFFER_SIZE - 1) + "\u{e9} end\n";
+        let info = count(Cursor::new(&text), Wanted::ALL).unwrap();
+        assert_eq!(info.num_words, 2);
+        assert_eq!(info.num_chars, BUFFER_SIZE + 5);
+        assert_eq!(info.max_line_length, BUFFER_SIZE + 4);
     }
 }
@@ -3,12 +3,13 @@ use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
 use rand::{distributions::Alphanumeric, Rng};
-use std::fs;
+use std::{ffi::OsStr, fs, os::unix::ffi::OsStrExt};
 
 const PRG: &str = "wcr";
 const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const ATLAMAL: &str = "tests/inputs/atlamal.txt";
+const FILES0: &str = "tests/inputs/files0.txt";
 
 // --------------------------------------------------
 fn gen_bad_file() -> String {
@@ -54,15 +55,33 @@ fn run(args: &[&str], expected_file: &str) -> Result<()> {
 #[test]
 fn skips_bad_file() -> Result<()> {
     let bad = gen_bad_file();
-    let expected = format!("{bad}: .* [(]os error 2[)]");
+    let expected = format!("{PRG}: {bad}: .* [(]os error 2[)]");
     Command::cargo_bin(PRG)?
-        .arg(bad)
+        .args([&bad, FOX])
         .assert()
-        .success()
+        .failure()
+        .code(1)
+        .stdout(" 1  9 48 tests/inputs/fox.txt\n 1  9 48 total\n")
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn directory() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/fox-dir.out")?;
+    Command::cargo_bin(PRG)?
+        .args([FOX, "tests/inputs"])
+        .assert()
+        .failure()
+        .code(1)
+        .stdout(expected)
+        .stderr(predicate::str::contains(
+            "wcr: tests/inputs: Is a directory",
+        ));
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn empty() -> Result<()> {
@@ -117,6 +136,12 @@ fn fox_bytes_lines() -> Result<()> {
     run(&["-l", "-c", FOX], "tests/expected/fox.txt.cl.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn fox_max_line_length() -> Result<()> {
+    run(&["--max-line-length", FOX], "tests/expected/fox.txt.L.out")
+}
+
 // --------------------------------------------------
 #[test]
 fn atlamal() -> Result<()> {
@@ -159,6 +184,24 @@ fn atlamal_bytes_lines() -> Result<()> {
     run(&["-l", "-c", ATLAMAL], "tests/expected/atlamal.txt.cl.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn atlamal_max_line_length() -> Result<()> {
+    run(&["-L", ATLAMAL], "tests/expected/atlamal.txt.L.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn atlamal_chars() -> Result<()> {
+    run(&["-m", ATLAMAL], "tests/expected/atlamal.txt.m.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn atlamal_lines_words_chars() -> Result<()> {
+    run(&["-lwm", ATLAMAL], "tests/expected/atlamal.txt.lwm.out")
+}
+
 // --------------------------------------------------
 #[test]
 fn atlamal_stdin() -> Result<()> {
@@ -177,6 +220,24 @@ fn atlamal_stdin() -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn stdin_with_files() -> Result<()> {
+    let input = fs::read_to_string(ATLAMAL)?;
+    let expected = fs::read_to_string("tests/expected/fox-stdin.out")?;
+
+    let output = Command::cargo_bin(PRG)?
+        .args([FOX, "-"])
+        .write_stdin(input)
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("invalid UTF-8");
+    assert_eq!(stdout, expected);
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn test_all() -> Result<()> {
@@ -195,6 +256,18 @@ fn test_all_words() -> Result<()> {
     run(&["-w", EMPTY, FOX, ATLAMAL], "tests/expected/all.w.out")
 }
 
+// --------------------------------------------------
+#[test]
+fn test_all_chars() -> Result<()> {
+    run(&["-m", EMPTY, FOX, ATLAMAL], "tests/expected/all.m.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_chars_lines() -> Result<()> {
+    run(&["-ml", EMPTY, FOX, ATLAMAL], "tests/expected/all.ml.out")
+}
+
 // --------------------------------------------------
 #[test]
 fn test_all_bytes() -> Result<()> {
@@ -218,3 +291,122 @@ fn test_all_words_lines() -> Result<()> {
 fn test_all_bytes_lines() -> Result<()> {
     run(&["-cl", EMPTY, FOX, ATLAMAL], "tests/expected/all.cl.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn test_all_max_line_length() -> Result<()> {
+    run(&["-L", EMPTY, FOX, ATLAMAL], "tests/expected/all.L.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from() -> Result<()> {
+    run(&["--files0-from", FILES0], "tests/expected/files0.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_stdin() -> Result<()> {
+    let input = fs::read(FILES0)?;
+    let expected = fs::read_to_string("tests/expected/files0.stdin.out")?;
+    let output = Command::cargo_bin(PRG)?
+        .args(["--files0-from=-"])
+        .write_stdin(input)
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).expect("invalid UTF-8");
+    assert_eq!(stdout, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn files0_from_non_utf8_name() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let path = dir.path().join(OsStr::from_bytes(b"caf\xe9.txt"));
+    fs::write(&path, "one line\n")?;
+
+    let name = path.as_os_str().as_bytes();
+    let output = Command::cargo_bin(PRG)?
+        .args(["-l", "--files0-from=-"])
+        .write_stdin([name, b"\0"].concat())
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, [b"1 ", name, b"\n"].concat());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_and_files() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from", FILES0, FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_files0_from_empty_name() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--files0-from=-"])
+        .write_stdin("tests/inputs/fox.txt\0\0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid zero-length file name"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn fox_json() -> Result<()> {
+    run(
+        &["--output-format", "json", FOX],
+        "tests/expected/fox.txt.json",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_json() -> Result<()> {
+    run(
+        &["--output-format", "json", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.json",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_csv() -> Result<()> {
+    run(
+        &["--output-format", "csv", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.csv",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn test_all_tsv() -> Result<()> {
+    run(
+        &["--output-format=tsv", "-l", EMPTY, FOX, ATLAMAL],
+        "tests/expected/all.tsv",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_output_format() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--output-format", "xml", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid value 'xml' for '--output-format <FORMAT>'",
+        ));
+    Ok(())
+}
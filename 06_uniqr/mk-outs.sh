@@ -13,6 +13,11 @@ echo -ne "a\na\nb"   > $ROOT/t4.txt
 echo -ne "b\na\na\n" > $ROOT/t5.txt
 echo -ne "a\nb\nc\n" > $ROOT/t6.txt
 
+# Only the line terminator is insignificant
+echo -ne "foo \nfoo\nfoo\t\nfoo\n"    > $ROOT/spaces.txt
+echo -ne "caf\xe9\ncaf\xe9\ncafe\n" > $ROOT/latin1.txt
+echo -ne "a\0a\0b\nb\0b\nb"        > $ROOT/nul.txt
+
 for FILE in $ROOT/*.txt; do
     BASENAME=$(basename "$FILE")
     uniq      $FILE > ${OUT_DIR}/${BASENAME}.out
@@ -20,3 +25,49 @@ for FILE in $ROOT/*.txt; do
     uniq    < $FILE > ${OUT_DIR}/${BASENAME}.stdin.out
     uniq -c < $FILE > ${OUT_DIR}/${BASENAME}.stdin.c.out
 done
+
+# Comparison controls
+KEYS="$ROOT/keys.txt"
+uniq -d     $KEYS > ${OUT_DIR}/keys.txt.d.out
+uniq -D     $KEYS > ${OUT_DIR}/keys.txt.D.out
+uniq -u     $KEYS > ${OUT_DIR}/keys.txt.u.out
+uniq -d -u  $KEYS > ${OUT_DIR}/keys.txt.du.out
+uniq -i     $KEYS > ${OUT_DIR}/keys.txt.i.out
+uniq -i -D  $KEYS > ${OUT_DIR}/keys.txt.iD.out
+uniq -f 1   $KEYS > ${OUT_DIR}/keys.txt.f1.out
+uniq -f 2   $KEYS > ${OUT_DIR}/keys.txt.f2.out
+uniq -s 2   $KEYS > ${OUT_DIR}/keys.txt.s2.out
+uniq -w 4   $KEYS > ${OUT_DIR}/keys.txt.w4.out
+uniq -s 4 -w 1 $KEYS > ${OUT_DIR}/keys.txt.s4w1.out
+uniq -f 1 -u $KEYS > ${OUT_DIR}/keys.txt.f1u.out
+
+# Groups
+for METHOD in separate prepend append both; do
+    uniq --group=$METHOD $KEYS > ${OUT_DIR}/keys.txt.group-${METHOD}.out
+done
+
+for METHOD in none prepend separate; do
+    uniq --all-repeated=$METHOD -i $KEYS \
+        > ${OUT_DIR}/keys.txt.iD-${METHOD}.out
+done
+
+# Global dedup in order of first occurrence
+GLOBAL="$ROOT/global.txt"
+COUNT='{ if (!($0 in n)) o[++k] = $0; n[$0]++ }
+    END { for (i = 1; i <= k; i++) printf "%4d %s\n", n[o[i]], o[i] }'
+awk '!seen[$0]++' $GLOBAL > ${OUT_DIR}/global.txt.global.out
+awk "$COUNT" $GLOBAL > ${OUT_DIR}/global.txt.global.c.out
+awk "$COUNT" $GLOBAL | sort -s -k1,1nr > ${OUT_DIR}/global.txt.global.cs.out
+awk "$COUNT" $GLOBAL | awk '$1 == 1' | cut -c6- \
+    > ${OUT_DIR}/global.txt.global.u.out
+awk "$COUNT" $GLOBAL | awk '$1 > 1' | cut -c6- \
+    > ${OUT_DIR}/global.txt.global.d.out
+
+# Byte-level comparison and NUL-terminated lines
+uniq $ROOT/spaces.txt > ${OUT_DIR}/spaces.txt.out
+uniq $ROOT/latin1.txt > ${OUT_DIR}/latin1.txt.out
+uniq -D $ROOT/latin1.txt > ${OUT_DIR}/latin1.txt.D.out
+uniq -z $ROOT/nul.txt > ${OUT_DIR}/nul.txt.z.out
+uniq -z --group=append $ROOT/nul.txt > ${OUT_DIR}/nul.txt.z.group-append.out
+uniq -D $ROOT/t4.txt > ${OUT_DIR}/t4.txt.D.out
+uniq --group=append $ROOT/t4.txt > ${OUT_DIR}/t4.txt.group-append.out
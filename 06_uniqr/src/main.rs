@@ -1,8 +1,13 @@
-use anyhow::{anyhow, Result};
-use clap::Parser;
+use anyhow::{anyhow, bail, Result};
+use clap::{Parser, ValueEnum};
+use cliutil::{open, Reporter};
 use std::{
+    borrow::Cow,
+    cmp::Reverse,
+    collections::{hash_map::DefaultHasher, BinaryHeap, HashMap},
     fs::File,
-    io::{self, BufRead, BufReader, Write},
+    hash::{Hash, Hasher},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, Write},
 };
 
 #[derive(Debug, Parser)]
@@ -20,14 +25,98 @@ struct Args {
     /// Show counts
     #[arg(short, long)]
     count: bool,
+
+    /// Only print duplicate lines, one for each group
+    #[arg(short('d'), long)]
+    repeated: bool,
+
+    /// Print all duplicate lines, delimiting groups with empty lines
+    #[arg(
+        short('D'),
+        long,
+        value_name = "METHOD",
+        num_args(0..=1),
+        require_equals(true),
+        default_missing_value("none"),
+        conflicts_with_all(["count", "repeated", "unique"])
+    )]
+    all_repeated: Option<Delimit>,
+
+    /// Show all lines, separating groups with empty lines
+    #[arg(
+        long,
+        value_name = "METHOD",
+        num_args(0..=1),
+        require_equals(true),
+        default_missing_value("separate"),
+        conflicts_with_all(["count", "repeated", "all_repeated", "unique"])
+    )]
+    group: Option<Group>,
+
+    /// Only print unique lines
+    #[arg(short, long)]
+    unique: bool,
+
+    /// Ignore differences in case when comparing
+    #[arg(short, long)]
+    ignore_case: bool,
+
+    /// Avoid comparing the first N fields
+    #[arg(short('f'), long, value_name = "N", default_value = "0")]
+    skip_fields: usize,
+
+    /// Avoid comparing the first N characters
+    #[arg(short('s'), long, value_name = "N", default_value = "0")]
+    skip_chars: usize,
+
+    /// Compare no more than N characters
+    #[arg(short('w'), long, value_name = "N")]
+    check_chars: Option<usize>,
+
+    /// Remove duplicates anywhere in the input, keeping first occurrences
+    #[arg(long, conflicts_with_all(["all_repeated", "group"]))]
+    global: bool,
+
+    /// With --global, order lines by descending count
+    #[arg(long, requires("global"))]
+    sort_by_count: bool,
+
+    /// With --global, spill to disk past this many bytes (K, M, G suffix)
+    #[arg(
+        long,
+        value_name = "SIZE",
+        requires("global"),
+        value_parser(parse_size)
+    )]
+    max_memory: Option<u64>,
+
+    /// Line delimiter is NUL, not newline
+    #[arg(short, long)]
+    zero_terminated: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Delimit {
+    None,
+    Prepend,
+    Separate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Group {
+    Separate,
+    Prepend,
+    Append,
+    Both,
 }
 
 // --------------------------------------------------
 fn main() {
+    let mut reporter = Reporter::new(env!("CARGO_BIN_NAME"));
     if let Err(e) = run(Args::parse()) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+        reporter.report(e);
     }
+    std::process::exit(reporter.exit_code());
 }
 
 // --------------------------------------------------
@@ -35,49 +124,492 @@ fn run(args: Args) -> Result<()> {
     let mut file =
         open(&args.in_file).map_err(|e| anyhow!("{}: {e}", args.in_file))?;
 
-    let mut out_file: Box<dyn Write> = match &args.out_file {
+    let out_file: Box<dyn Write> = match &args.out_file {
         Some(out_name) => Box::new(File::create(out_name)?),
         _ => Box::new(io::stdout()),
     };
 
-    let mut print = |num: u64, text: &str| -> Result<()> {
-        if num > 0 {
-            if args.count {
-                write!(out_file, "{num:>4} {text}")?;
-            } else {
-                write!(out_file, "{text}")?;
+    let mut printer = Printer::new(&args, out_file);
+    if args.global {
+        run_global(file, &args, &mut printer)?;
+        return printer.finish();
+    }
+
+    let mut line = vec![];
+    while read_line(&mut file, &mut line, printer.delimiter)? {
+        if printer.continues(&line) {
+            printer.repeat(&line)?;
+        } else {
+            printer.end()?;
+            printer.start(&line)?;
+        }
+    }
+
+    printer.end()?;
+    printer.finish()?;
+
+    Ok(())
+}
+
+// --------------------------------------------------
+// Writes each run of matching lines according to the output options, one
+// line at a time. Only the first line of a run and its count are kept.
+struct Printer<'a> {
+    args: &'a Args,
+    out_file: Box<dyn Write>,
+    delimiter: u8,
+    groups: u64,
+    first: Vec<u8>,
+    count: u64,
+}
+
+impl<'a> Printer<'a> {
+    fn new(args: &'a Args, out_file: Box<dyn Write>) -> Self {
+        Printer {
+            args,
+            out_file,
+            delimiter: if args.zero_terminated { b'\0' } else { b'\n' },
+            groups: 0,
+            first: vec![],
+            count: 0,
+        }
+    }
+
+    // Whether a line belongs to the current run
+    fn continues(&self, line: &[u8]) -> bool {
+        self.count > 0 && key(line, self.args) == key(&self.first, self.args)
+    }
+
+    // A line that starts a new run
+    fn start(&mut self, line: &[u8]) -> Result<()> {
+        self.first.clear();
+        self.first.extend_from_slice(line);
+        self.count = 1;
+        if self.args.group.is_some() {
+            self.start_group()?;
+            self.print_line(line)?;
+        }
+        Ok(())
+    }
+
+    // A line that repeats the current run. With --all-repeated, the first
+    // line is held back until it's known to be repeated.
+    fn repeat(&mut self, line: &[u8]) -> Result<()> {
+        self.count += 1;
+        if self.args.group.is_some() {
+            self.print_line(line)?;
+        } else if self.args.all_repeated.is_some() {
+            if self.count == 2 {
+                self.start_group()?;
+                let first = std::mem::take(&mut self.first);
+                self.print_line(&first)?;
+                self.first = first;
             }
+            self.print_line(line)?;
+        }
+        Ok(())
+    }
+
+    // The current run is over
+    fn end(&mut self) -> Result<()> {
+        let count = std::mem::take(&mut self.count);
+        if count > 0
+            && self.args.group.is_none()
+            && self.args.all_repeated.is_none()
+        {
+            let first = std::mem::take(&mut self.first);
+            self.print_count(count, &first)?;
+            self.first = first;
+        }
+        Ok(())
+    }
+
+    fn print_count(&mut self, num: u64, text: &[u8]) -> Result<()> {
+        let show = match (self.args.repeated, self.args.unique) {
+            (true, true) => false,
+            (true, false) => num > 1,
+            (false, true) => num == 1,
+            (false, false) => true,
         };
+
+        if show {
+            if self.args.count {
+                write!(self.out_file, "{num:>4} ")?;
+            }
+            self.print_line(text)?;
+        }
         Ok(())
-    };
+    }
+
+    // Separate a group of lines from those before it
+    fn start_group(&mut self) -> Result<()> {
+        let (prepend, separate) =
+            match (self.args.group, self.args.all_repeated) {
+                (Some(group), _) => {
+                    (matches!(group, Group::Prepend | Group::Both), true)
+                }
+                (_, Some(delimit)) => {
+                    (delimit == Delimit::Prepend, delimit != Delimit::None)
+                }
+                _ => (false, false),
+            };
+
+        if prepend || (separate && self.groups > 0) {
+            self.print_line(b"")?;
+        }
+        self.groups += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        let append =
+            matches!(self.args.group, Some(Group::Append | Group::Both));
+        if append && self.groups > 0 {
+            self.print_line(b"")?;
+        }
+        Ok(())
+    }
+
+    // Every line is terminated, even if the last input line was not
+    fn print_line(&mut self, text: &[u8]) -> Result<()> {
+        self.out_file.write_all(text)?;
+        self.out_file.write_all(&[self.delimiter])?;
+        Ok(())
+    }
+}
+
+// --------------------------------------------------
+// Prints the first occurrence of each line from anywhere in the input.
+// Lines are printed as soon as they are seen unless counts are needed.
+fn run_global(
+    mut file: impl BufRead,
+    args: &Args,
+    printer: &mut Printer,
+) -> Result<()> {
+    let stream =
+        !(args.count || args.repeated || args.unique || args.sort_by_count);
+
+    let mut table = Table::new(args);
+    let mut line = vec![];
+    let mut index = 0;
+    while read_line(&mut file, &mut line, printer.delimiter)? {
+        if table.insert(index, &line)? && stream {
+            printer.print_count(1, &line)?;
+        }
+
+        index += 1;
+    }
+
+    table.finish(printer, stream)
+}
+
+// --------------------------------------------------
+// A distinct line with its first position and number of occurrences
+struct Entry {
+    index: u64,
+    count: u64,
+    text: Vec<u8>,
+}
 
-    let mut line = String::new();
-    let mut previous = String::new();
-    let mut count: u64 = 0;
-    loop {
-        let bytes = file.read_line(&mut line)?;
-        if bytes == 0 {
-            break;
+const PARTITIONS: usize = 64;
+
+// Rough cost of a table entry beyond its text and key
+const ENTRY_OVERHEAD: u64 = 64;
+
+// --------------------------------------------------
+// Distinct lines in order of first occurrence. Once --max-memory is
+// exceeded, the table and all remaining lines are written to temporary
+// files partitioned by key, and each partition is counted on its own at
+// the end.
+struct Table<'a> {
+    args: &'a Args,
+    seen: HashMap<Vec<u8>, usize>,
+    entries: Vec<Entry>,
+    memory: u64,
+    partitions: Vec<BufWriter<File>>,
+    spilled_at: u64,
+}
+
+impl<'a> Table<'a> {
+    fn new(args: &'a Args) -> Self {
+        Table {
+            args,
+            seen: HashMap::new(),
+            entries: vec![],
+            memory: 0,
+            partitions: vec![],
+            spilled_at: 0,
+        }
+    }
+
+    // Returns true for the first occurrence of a line kept in memory
+    fn insert(&mut self, index: u64, text: &[u8]) -> Result<bool> {
+        if !self.partitions.is_empty() {
+            let part = partition(&key(text, self.args));
+            write_entry(&mut self.partitions[part], index, 1, text)?;
+            return Ok(false);
+        }
+
+        if !self.add(Entry {
+            index,
+            count: 1,
+            text: text.to_vec(),
+        }) {
+            return Ok(false);
         }
 
-        if line.trim_end() != previous.trim_end() {
-            print(count, &previous)?;
-            previous = line.clone();
-            count = 0;
+        if self.args.max_memory.is_some_and(|max| self.memory > max) {
+            self.spill(index + 1)?;
         }
+        Ok(true)
+    }
+
+    // Returns true if the entry's key was not seen before
+    fn add(&mut self, entry: Entry) -> bool {
+        let key = key(&entry.text, self.args);
+        if let Some(&i) = self.seen.get(key.as_ref()) {
+            let found = &mut self.entries[i];
+            found.count += entry.count;
+            if entry.index < found.index {
+                found.index = entry.index;
+                found.text = entry.text;
+            }
+            return false;
+        }
+
+        self.memory += (key.len() + entry.text.len()) as u64 + ENTRY_OVERHEAD;
+        self.seen.insert(key.into_owned(), self.entries.len());
+        self.entries.push(entry);
+        true
+    }
+
+    fn spill(&mut self, spilled_at: u64) -> Result<()> {
+        self.partitions = (0..PARTITIONS)
+            .map(|_| tempfile::tempfile().map(BufWriter::new))
+            .collect::<io::Result<_>>()?;
 
-        count += 1;
-        line.clear();
+        for entry in self.entries.drain(..) {
+            let part = partition(&key(&entry.text, self.args));
+            write_entry(
+                &mut self.partitions[part],
+                entry.index,
+                entry.count,
+                &entry.text,
+            )?;
+        }
+
+        self.seen.clear();
+        self.memory = 0;
+        self.spilled_at = spilled_at;
+        Ok(())
     }
-    print(count, &previous)?;
 
+    fn finish(mut self, printer: &mut Printer, stream: bool) -> Result<()> {
+        let sort_by_count = self.args.sort_by_count;
+        let order = |entry: &Entry| {
+            let count = if sort_by_count { entry.count } else { 0 };
+            (Reverse(count), entry.index)
+        };
+
+        if self.partitions.is_empty() {
+            if !stream {
+                self.entries.sort_by_key(order);
+                for entry in &self.entries {
+                    printer.print_count(entry.count, &entry.text)?;
+                }
+            }
+            return Ok(());
+        }
+
+        // Count each partition and write it back out in output order
+        let mut sorted = vec![];
+        for part in std::mem::take(&mut self.partitions) {
+            let mut reader = rewind(part)?;
+            while let Some(entry) = read_entry(&mut reader)? {
+                self.add(entry);
+                if let Some(max) = self.args.max_memory {
+                    if self.memory > max {
+                        bail!(
+                            "--max-memory of {max} bytes is too small to \
+                            count the distinct lines of the input"
+                        );
+                    }
+                }
+            }
+
+            self.entries.sort_by_key(order);
+            let mut file = BufWriter::new(tempfile::tempfile()?);
+            for entry in self.entries.drain(..) {
+                write_entry(
+                    &mut file,
+                    entry.index,
+                    entry.count,
+                    &entry.text,
+                )?;
+            }
+            sorted.push(rewind(file)?);
+            self.seen.clear();
+            self.memory = 0;
+        }
+
+        // Merge the sorted partitions
+        let mut heads = sorted
+            .iter_mut()
+            .map(read_entry)
+            .collect::<Result<Vec<_>>>()?;
+        let mut heap: BinaryHeap<_> = heads
+            .iter()
+            .enumerate()
+            .filter_map(|(i, head)| {
+                head.as_ref().map(|entry| Reverse((order(entry), i)))
+            })
+            .collect();
+
+        while let Some(Reverse((_, i))) = heap.pop() {
+            if let Some(entry) = heads[i].take() {
+                if !stream || entry.index >= self.spilled_at {
+                    printer.print_count(entry.count, &entry.text)?;
+                }
+            }
+
+            heads[i] = read_entry(&mut sorted[i])?;
+            if let Some(entry) = &heads[i] {
+                heap.push(Reverse((order(entry), i)));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// --------------------------------------------------
+fn partition(key: &[u8]) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % PARTITIONS as u64) as usize
+}
+
+// --------------------------------------------------
+fn rewind(file: BufWriter<File>) -> Result<BufReader<File>> {
+    let mut file = file.into_inner().map_err(|e| e.into_error())?;
+    file.rewind()?;
+    Ok(BufReader::new(file))
+}
+
+// --------------------------------------------------
+fn write_entry(
+    file: &mut impl Write,
+    index: u64,
+    count: u64,
+    text: &[u8],
+) -> Result<()> {
+    file.write_all(&index.to_le_bytes())?;
+    file.write_all(&count.to_le_bytes())?;
+    file.write_all(&(text.len() as u64).to_le_bytes())?;
+    file.write_all(text)?;
     Ok(())
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> Result<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+fn read_entry(file: &mut impl Read) -> Result<Option<Entry>> {
+    let mut buf = [0; 8];
+    match file.read_exact(&mut buf) {
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+            return Ok(None)
+        }
+        res => res?,
+    }
+    let index = u64::from_le_bytes(buf);
+
+    file.read_exact(&mut buf)?;
+    let count = u64::from_le_bytes(buf);
+
+    file.read_exact(&mut buf)?;
+    let mut text = vec![0; u64::from_le_bytes(buf) as usize];
+    file.read_exact(&mut text)?;
+
+    Ok(Some(Entry { index, count, text }))
+}
+
+// --------------------------------------------------
+// Parses a number of bytes with an optional K, M or G suffix
+fn parse_size(val: &str) -> Result<u64> {
+    let (num, multiplier) = match val.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&val[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&val[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&val[..i], 1 << 30),
+        _ => (val, 1),
+    };
+
+    num.parse::<u64>()
+        .ok()
+        .and_then(|num| num.checked_mul(multiplier))
+        .ok_or_else(|| anyhow!("invalid size \"{val}\""))
+}
+
+// --------------------------------------------------
+// Reads the next line without its delimiter. The last line need not end
+// with the delimiter.
+fn read_line(
+    file: &mut impl BufRead,
+    line: &mut Vec<u8>,
+    delimiter: u8,
+) -> Result<bool> {
+    line.clear();
+    if file.read_until(delimiter, line)? == 0 {
+        return Ok(false);
+    }
+
+    if line.last() == Some(&delimiter) {
+        line.pop();
+    }
+    Ok(true)
+}
+
+// --------------------------------------------------
+// The part of a line that is compared to find duplicates
+fn key<'a>(line: &'a [u8], args: &Args) -> Cow<'a, [u8]> {
+    let is_blank = |c: &u8| *c == b' ' || *c == b'\t';
+    let mut text = line;
+    for _ in 0..args.skip_fields {
+        let start = text.iter().position(|c| !is_blank(c));
+        text = &text[start.unwrap_or(text.len())..];
+        let end = text.iter().position(is_blank);
+        text = &text[end.unwrap_or(text.len())..];
+    }
+
+    text = &text[args.skip_chars.min(text.len())..];
+    if let Some(num) = args.check_chars {
+        text = &text[..num.min(text.len())];
+    }
+
+    if args.ignore_case {
+        Cow::Owned(text.to_ascii_lowercase())
+    } else {
+        Cow::Borrowed(text)
+    }
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::parse_size;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("0").unwrap(), 0);
+        assert_eq!(parse_size("100").unwrap(), 100);
+        assert_eq!(parse_size("8K").unwrap(), 8192);
+        assert_eq!(parse_size("2m").unwrap(), 2 * 1024 * 1024);
+        assert_eq!(parse_size("1G").unwrap(), 1024 * 1024 * 1024);
+
+        let res = parse_size("foo");
+        assert!(res.is_err());
+        assert_eq!(res.unwrap_err().to_string(), r#"invalid size "foo""#);
+
+        assert!(parse_size("").is_err());
+        assert!(parse_size("K").is_err());
+        assert!(parse_size("-1").is_err());
+        assert!(parse_size("20000000000G").is_err());
     }
 }
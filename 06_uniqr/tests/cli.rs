@@ -80,6 +80,9 @@ const T6: Test = Test {
     out_count: "tests/expected/t6.txt.c.out",
 };
 
+const KEYS: &str = "tests/inputs/keys.txt";
+const GLOBAL: &str = "tests/inputs/global.txt";
+
 // --------------------------------------------------
 fn gen_bad_file() -> String {
     loop {
@@ -99,11 +102,12 @@ fn gen_bad_file() -> String {
 #[test]
 fn dies_bad_file() -> Result<()> {
     let bad = gen_bad_file();
-    let expected = format!("{bad}: .* [(]os error 2[)]");
+    let expected = format!("{PRG}: {bad}: .* [(]os error 2[)]");
     Command::cargo_bin(PRG)?
         .arg(bad)
         .assert()
         .failure()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -222,6 +226,19 @@ fn run_stdin_outfile_count(test: &Test) -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+fn run_args(input: &str, args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read(expected_file)?;
+    let output = Command::cargo_bin(PRG)?
+        .args(args)
+        .arg(input)
+        .output()
+        .expect("fail");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn empty() -> Result<()> {
@@ -617,3 +634,354 @@ fn t6_outfile_count() -> Result<()> {
 fn t6_stdin_outfile_count() -> Result<()> {
     run_stdin_outfile_count(&T6)
 }
+
+// --------------------------------------------------
+#[test]
+fn keys_repeated() -> Result<()> {
+    run_args(KEYS, &["-d"], "tests/expected/keys.txt.d.out")
+}
+
+#[test]
+fn keys_all_repeated() -> Result<()> {
+    run_args(KEYS, &["-D"], "tests/expected/keys.txt.D.out")
+}
+
+#[test]
+fn keys_unique() -> Result<()> {
+    run_args(KEYS, &["--unique"], "tests/expected/keys.txt.u.out")
+}
+
+#[test]
+fn keys_repeated_unique() -> Result<()> {
+    run_args(KEYS, &["-d", "-u"], "tests/expected/keys.txt.du.out")
+}
+
+#[test]
+fn keys_ignore_case() -> Result<()> {
+    run_args(KEYS, &["-i"], "tests/expected/keys.txt.i.out")
+}
+
+#[test]
+fn keys_ignore_case_all_repeated() -> Result<()> {
+    run_args(
+        KEYS,
+        &["-i", "--all-repeated"],
+        "tests/expected/keys.txt.iD.out",
+    )
+}
+
+#[test]
+fn keys_skip_fields() -> Result<()> {
+    run_args(KEYS, &["-f", "1"], "tests/expected/keys.txt.f1.out")
+}
+
+#[test]
+fn keys_skip_fields2() -> Result<()> {
+    run_args(
+        KEYS,
+        &["--skip-fields", "2"],
+        "tests/expected/keys.txt.f2.out",
+    )
+}
+
+#[test]
+fn keys_skip_chars() -> Result<()> {
+    run_args(KEYS, &["-s", "2"], "tests/expected/keys.txt.s2.out")
+}
+
+#[test]
+fn keys_check_chars() -> Result<()> {
+    run_args(KEYS, &["-w", "4"], "tests/expected/keys.txt.w4.out")
+}
+
+#[test]
+fn keys_skip_chars_check_chars() -> Result<()> {
+    run_args(
+        KEYS,
+        &["-s", "4", "-w", "1"],
+        "tests/expected/keys.txt.s4w1.out",
+    )
+}
+
+#[test]
+fn keys_skip_fields_unique() -> Result<()> {
+    run_args(KEYS, &["-f", "1", "-u"], "tests/expected/keys.txt.f1u.out")
+}
+
+#[test]
+fn dies_all_repeated_count() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-D", "-c", KEYS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+#[test]
+fn keys_group() -> Result<()> {
+    run_args(
+        KEYS,
+        &["--group"],
+        "tests/expected/keys.txt.group-separate.out",
+    )
+}
+
+#[test]
+fn keys_group_separate() -> Result<()> {
+    run_args(
+        KEYS,
+        &["--group=separate"],
+        "tests/expected/keys.txt.group-separate.out",
+    )
+}
+
+#[test]
+fn keys_group_prepend() -> Result<()> {
+    run_args(
+        KEYS,
+        &["--group=prepend"],
+        "tests/expected/keys.txt.group-prepend.out",
+    )
+}
+
+#[test]
+fn keys_group_append() -> Result<()> {
+    run_args(
+        KEYS,
+        &["--group=append"],
+        "tests/expected/keys.txt.group-append.out",
+    )
+}
+
+#[test]
+fn keys_group_both() -> Result<()> {
+    run_args(
+        KEYS,
+        &["--group=both"],
+        "tests/expected/keys.txt.group-both.out",
+    )
+}
+
+#[test]
+fn keys_all_repeated_none() -> Result<()> {
+    run_args(
+        KEYS,
+        &["-i", "--all-repeated=none"],
+        "tests/expected/keys.txt.iD-none.out",
+    )
+}
+
+#[test]
+fn keys_all_repeated_prepend() -> Result<()> {
+    run_args(
+        KEYS,
+        &["-i", "--all-repeated=prepend"],
+        "tests/expected/keys.txt.iD-prepend.out",
+    )
+}
+
+#[test]
+fn keys_all_repeated_separate() -> Result<()> {
+    run_args(
+        KEYS,
+        &["-i", "--all-repeated=separate"],
+        "tests/expected/keys.txt.iD-separate.out",
+    )
+}
+
+#[test]
+fn empty_group_both() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--group=both", EMPTY.input])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+#[test]
+fn dies_group_unique() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--group", "-u", KEYS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+#[test]
+fn dies_bad_group() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--group=middle", KEYS])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value 'middle'"));
+    Ok(())
+}
+
+#[test]
+fn global() -> Result<()> {
+    run_args(
+        GLOBAL,
+        &["--global"],
+        "tests/expected/global.txt.global.out",
+    )
+}
+
+#[test]
+fn global_count() -> Result<()> {
+    run_args(
+        GLOBAL,
+        &["--global", "-c"],
+        "tests/expected/global.txt.global.c.out",
+    )
+}
+
+#[test]
+fn global_sort_by_count() -> Result<()> {
+    run_args(
+        GLOBAL,
+        &["--global", "-c", "--sort-by-count"],
+        "tests/expected/global.txt.global.cs.out",
+    )
+}
+
+#[test]
+fn global_unique() -> Result<()> {
+    run_args(
+        GLOBAL,
+        &["--global", "-u"],
+        "tests/expected/global.txt.global.u.out",
+    )
+}
+
+#[test]
+fn global_repeated() -> Result<()> {
+    run_args(
+        GLOBAL,
+        &["--global", "-d"],
+        "tests/expected/global.txt.global.d.out",
+    )
+}
+
+#[test]
+fn global_max_memory() -> Result<()> {
+    let input: String =
+        (0..20000).map(|num| format!("{}\n", num % 3000)).collect();
+
+    for args in [
+        vec!["--global"],
+        vec!["--global", "-c"],
+        vec!["--global", "-c", "--sort-by-count"],
+    ] {
+        let expected = Command::cargo_bin(PRG)?
+            .args(&args)
+            .write_stdin(input.clone())
+            .output()?;
+        assert!(expected.status.success());
+
+        let output = Command::cargo_bin(PRG)?
+            .args(&args)
+            .arg("--max-memory=64K")
+            .write_stdin(input.clone())
+            .output()?;
+        assert!(output.status.success());
+        assert_eq!(output.stdout, expected.stdout);
+    }
+    Ok(())
+}
+
+#[test]
+fn dies_global_max_memory() -> Result<()> {
+    let input: String = (0..20000).map(|num| format!("{num}\n")).collect();
+    Command::cargo_bin(PRG)?
+        .args(["--global", "--max-memory=1K"])
+        .write_stdin(input)
+        .assert()
+        .failure()
+        .stderr(
+            "uniqr: --max-memory of 1024 bytes is too small to count the \
+            distinct lines of the input\n",
+        );
+    Ok(())
+}
+
+#[test]
+fn dies_max_memory_without_global() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--max-memory=1K", GLOBAL])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--global"));
+    Ok(())
+}
+
+#[test]
+fn trailing_whitespace_is_significant() -> Result<()> {
+    run_args(
+        "tests/inputs/spaces.txt",
+        &[],
+        "tests/expected/spaces.txt.out",
+    )
+}
+
+#[test]
+fn non_utf8() -> Result<()> {
+    run_args(
+        "tests/inputs/latin1.txt",
+        &[],
+        "tests/expected/latin1.txt.out",
+    )
+}
+
+#[test]
+fn non_utf8_all_repeated() -> Result<()> {
+    run_args(
+        "tests/inputs/latin1.txt",
+        &["-D"],
+        "tests/expected/latin1.txt.D.out",
+    )
+}
+
+#[test]
+fn zero_terminated() -> Result<()> {
+    run_args(
+        "tests/inputs/nul.txt",
+        &["-z"],
+        "tests/expected/nul.txt.z.out",
+    )
+}
+
+#[test]
+fn zero_terminated_group_append() -> Result<()> {
+    run_args(
+        "tests/inputs/nul.txt",
+        &["--zero-terminated", "--group=append"],
+        "tests/expected/nul.txt.z.group-append.out",
+    )
+}
+
+#[test]
+fn zero_terminated_global() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-z", "--global", "tests/inputs/nul.txt"])
+        .assert()
+        .success()
+        .stdout("a\0b\nb\0");
+    Ok(())
+}
+
+#[test]
+fn t4_all_repeated() -> Result<()> {
+    run_args(T4.input, &["-D"], "tests/expected/t4.txt.D.out")
+}
+
+#[test]
+fn t4_group_append() -> Result<()> {
+    run_args(
+        T4.input,
+        &["--group=append"],
+        "tests/expected/t4.txt.group-append.out",
+    )
+}
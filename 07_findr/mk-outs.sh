@@ -39,3 +39,34 @@ find "$IN_DIR/a" "$IN_DIR/d" -name \*.txt > "$OUT_DIR/name_txt_path_a_d.txt"
 find "$IN_DIR" -name a* > "$OUT_DIR/name_a.txt"
 find "$IN_DIR" -type f -name a* > "$OUT_DIR/type_f_name_a.txt"
 find "$IN_DIR" -type d -name a* > "$OUT_DIR/type_d_name_a.txt"
+
+find "$IN_DIR" -maxdepth 1 > "$OUT_DIR/maxdepth_1.txt"
+find "$IN_DIR" -mindepth 2 -maxdepth 2 > "$OUT_DIR/mindepth_2_maxdepth_2.txt"
+find "$IN_DIR" -mindepth 3 > "$OUT_DIR/mindepth_3.txt"
+find "$IN_DIR" -maxdepth 2 -name "*.csv" > "$OUT_DIR/maxdepth_2_name_csv.txt"
+find "$IN_DIR" -type f -size 2c > "$OUT_DIR/type_f_size_2c.txt"
+
+find "$IN_DIR" \( -name \*.csv -o -name \*.mp3 \) -a -not -type l \
+    > "$OUT_DIR/expr_csv_mp3_not_l.txt"
+find "$IN_DIR" -type d -name a -o -type l > "$OUT_DIR/expr_d_a_or_l.txt"
+find "$IN_DIR/a" "$IN_DIR/d" ! \( -type d -o -name \*.txt \) -size -2k \
+    > "$OUT_DIR/expr_not_d_txt.txt"
+
+find "$IN_DIR" -type f -print0 > "$OUT_DIR/type_f_print0.txt"
+find "$IN_DIR" -type f -printf '%p|%f|%h|%P|%H|%d|%y|%s\n' \
+    > "$OUT_DIR/type_f_printf.txt"
+find "$IN_DIR" -name \*.csv -printf '[%-8f][%8y]\n' -o -type d -print \
+    > "$OUT_DIR/printf_csv_print_d.txt"
+find "$IN_DIR" -type f -exec echo found {} \; > "$OUT_DIR/exec_echo.txt"
+find "$IN_DIR" -type f -execdir echo {} \; > "$OUT_DIR/execdir_echo.txt"
+
+find "$IN_DIR" -path '*/a/*' > "$OUT_DIR/path_glob_a.txt"
+find "$IN_DIR" -ipath '*/B/*.csv' > "$OUT_DIR/ipath_b_csv.txt"
+find "$IN_DIR" -regex '.*/[a-c][.][a-z0-9]+' > "$OUT_DIR/regex_abc.txt"
+find "$IN_DIR" -name a -prune -o -print > "$OUT_DIR/prune_a.txt"
+find "$IN_DIR" -type d -name '[ad]' -prune > "$OUT_DIR/prune_d_print.txt"
+
+find -L "$IN_DIR" -type f > "$OUT_DIR/follow_type_f.txt"
+find -H "$IN_DIR/d/b.csv" -printf '%p %y\n' > "$OUT_DIR/follow_roots_b_csv.txt"
+
+find "$IN_DIR" | LC_ALL=C sort > "$OUT_DIR/sort.txt"
@@ -0,0 +1,576 @@
+use crate::walk::Entry;
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local};
+use cliutil::Reporter;
+use std::{
+    ffi::OsString,
+    fs::{self, FileType},
+    io::Write,
+    os::unix::{
+        ffi::OsStrExt,
+        fs::{FileTypeExt, MetadataExt},
+    },
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use users::{get_group_by_gid, get_user_by_uid};
+
+// Keep each batched command line well under the system's ARG_MAX
+const BATCH_SIZE: usize = 128 * 1024;
+
+// Conversions allowed after % in -printf
+const CONVERSIONS: &str = "acdfgGhHilmMnpPstuUy";
+
+// Time formats allowed after %A, %C and %T in -printf
+const TIME_FORMATS: &str = "@+aAbBcdDeFhHIjklmMprRsStTuUVwWxXyYzZ";
+
+// --------------------------------------------------
+// Where actions write their output and report their errors
+pub struct Context<'a> {
+    pub out: Box<dyn Write + Send + 'a>,
+    pub reporter: &'a mut Reporter,
+    pub root: PathBuf,
+    pub prune: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    Print,
+    Print0,
+    Printf(Vec<Piece>),
+    Delete,
+    Exec(Exec),
+    Prune,
+}
+
+impl Action {
+    pub fn run(
+        &mut self,
+        entry: &dyn Entry,
+        ctx: &mut Context,
+    ) -> Result<bool> {
+        match self {
+            Action::Print => {
+                ctx.out.write_all(entry.path().as_os_str().as_bytes())?;
+                ctx.out.write_all(b"\n")?;
+                Ok(true)
+            }
+            Action::Print0 => {
+                ctx.out.write_all(entry.path().as_os_str().as_bytes())?;
+                ctx.out.write_all(b"\0")?;
+                Ok(true)
+            }
+            Action::Printf(pieces) => {
+                match format(pieces, entry, &ctx.root) {
+                    Ok(text) => {
+                        ctx.out.write_all(&text)?;
+                        Ok(true)
+                    }
+                    Err(e) => {
+                        ctx.reporter.report_file(
+                            &entry.path().display().to_string(),
+                            e,
+                        );
+                        Ok(false)
+                    }
+                }
+            }
+            Action::Delete => {
+                // Keep what was printed about the entry ahead of any error
+                ctx.out.flush()?;
+                let path = entry.path();
+                let res = if entry.file_type().is_dir() {
+                    fs::remove_dir(path)
+                } else {
+                    fs::remove_file(path)
+                };
+                match res {
+                    Ok(_) => Ok(true),
+                    Err(e) => {
+                        ctx.reporter.report(format!(
+                            "cannot delete '{}': {e}",
+                            path.display()
+                        ));
+                        Ok(false)
+                    }
+                }
+            }
+            Action::Exec(exec) => exec.run(entry, ctx),
+            Action::Prune => {
+                ctx.prune = true;
+                Ok(true)
+            }
+        }
+    }
+
+    // Runs any commands still waiting for a batch to fill
+    pub fn finish(&mut self, ctx: &mut Context) -> Result<()> {
+        match self {
+            Action::Exec(exec) => exec.flush(ctx),
+            _ => Ok(()),
+        }
+    }
+}
+
+// --------------------------------------------------
+// A command for -exec or -execdir. With "+", paths are collected and passed
+// to as few commands as possible.
+#[derive(Debug, PartialEq)]
+pub struct Exec {
+    command: Vec<String>,
+    batch: bool,
+    in_dir: bool,
+    pending: Vec<OsString>,
+    pending_dir: Option<PathBuf>,
+    pending_len: usize,
+}
+
+impl Exec {
+    pub fn new(
+        command: Vec<String>,
+        batch: bool,
+        in_dir: bool,
+    ) -> Result<Self> {
+        if command.is_empty() || (batch && command.len() < 2) {
+            bail!("missing command");
+        }
+
+        if batch
+            && command[..command.len() - 1]
+                .iter()
+                .any(|a| a.contains("{}"))
+        {
+            bail!("only one instance of {{}} is supported with ... +");
+        }
+
+        Ok(Exec {
+            command,
+            batch,
+            in_dir,
+            pending: vec![],
+            pending_dir: None,
+            pending_len: 0,
+        })
+    }
+
+    fn run(&mut self, entry: &dyn Entry, ctx: &mut Context) -> Result<bool> {
+        // -execdir runs from the entry's directory with a path like "./name"
+        let (dir, path) = if self.in_dir {
+            let dir = entry
+                .path()
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            let name = Path::new(".").join(entry.file_name());
+            (Some(dir.to_path_buf()), name.into_os_string())
+        } else {
+            (None, entry.path().as_os_str().to_os_string())
+        };
+
+        if !self.batch {
+            let args = self.command[1..].iter().map(|arg| {
+                if arg == "{}" {
+                    path.clone()
+                } else {
+                    arg.replace("{}", &path.to_string_lossy()).into()
+                }
+            });
+            return spawn(&self.command[0], args, dir.as_deref(), ctx);
+        }
+
+        if !self.pending.is_empty()
+            && (self.pending_dir != dir || self.pending_len > BATCH_SIZE)
+        {
+            self.flush(ctx)?;
+        }
+
+        self.pending_len += path.len() + 1;
+        self.pending.push(path);
+        self.pending_dir = dir;
+        Ok(true)
+    }
+
+    fn flush(&mut self, ctx: &mut Context) -> Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let last = self.command.len() - 1;
+        let args: Vec<OsString> = self.command[1..last]
+            .iter()
+            .map(OsString::from)
+            .chain(self.pending.drain(..))
+            .collect();
+        let dir = self.pending_dir.take();
+        self.pending_len = 0;
+
+        // A failed batch does not change what matched, only the exit status
+        if !spawn(&self.command[0], args, dir.as_deref(), ctx)? {
+            ctx.reporter.fail();
+        }
+        Ok(())
+    }
+}
+
+// --------------------------------------------------
+fn spawn(
+    program: &str,
+    args: impl IntoIterator<Item = OsString>,
+    dir: Option<&Path>,
+    ctx: &mut Context,
+) -> Result<bool> {
+    // Keep our output ahead of the command's
+    ctx.out.flush()?;
+
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    if let Some(dir) = dir {
+        cmd.current_dir(dir);
+    }
+
+    match cmd.status() {
+        Ok(status) => Ok(status.success()),
+        Err(e) => {
+            ctx.reporter.report(format!("{program}: {e}"));
+            Ok(false)
+        }
+    }
+}
+
+// --------------------------------------------------
+// Literal text or a %-directive of a -printf format
+#[derive(Debug, PartialEq)]
+pub enum Piece {
+    Text(String),
+    Field {
+        left: bool,
+        width: usize,
+        conversion: char,
+        time_format: Option<char>,
+    },
+}
+
+// --------------------------------------------------
+pub fn parse_format(fmt: &str) -> Result<Vec<Piece>> {
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut chars = fmt.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => text.push(match chars.next() {
+                Some('n') => '\n',
+                Some('t') => '\t',
+                Some('r') => '\r',
+                Some('a') => '\x07',
+                Some('f') => '\x0c',
+                Some('v') => '\x0b',
+                Some('0') => '\0',
+                Some('\\') => '\\',
+                Some(c) => bail!("unknown escape '\\{c}'"),
+                None => bail!("format ends with '\\'"),
+            }),
+            '%' => {
+                if chars.next_if_eq(&'%').is_some() {
+                    text.push('%');
+                    continue;
+                }
+
+                let left = chars.next_if_eq(&'-').is_some();
+                let mut width = 0;
+                while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                    width = width * 10 + (digit as usize - '0' as usize);
+                }
+
+                let conversion = match chars.next() {
+                    Some(c) => c,
+                    _ => bail!("format ends with '%'"),
+                };
+
+                let time_format = match conversion {
+                    'A' | 'C' | 'T' => match chars.next() {
+                        Some(k) if TIME_FORMATS.contains(k) => Some(k),
+                        Some(k) => {
+                            bail!("unknown time format '%{conversion}{k}'")
+                        }
+                        _ => bail!("format ends with '%{conversion}'"),
+                    },
+                    c if CONVERSIONS.contains(c) => None,
+                    c => bail!("unknown directive '%{c}'"),
+                };
+
+                if !text.is_empty() {
+                    pieces.push(Piece::Text(std::mem::take(&mut text)));
+                }
+                pieces.push(Piece::Field {
+                    left,
+                    width,
+                    conversion,
+                    time_format,
+                });
+            }
+            _ => text.push(c),
+        }
+    }
+
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+    Ok(pieces)
+}
+
+// --------------------------------------------------
+fn format(
+    pieces: &[Piece],
+    entry: &dyn Entry,
+    root: &Path,
+) -> Result<Vec<u8>> {
+    let mut out = vec![];
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => out.extend_from_slice(text.as_bytes()),
+            Piece::Field {
+                left,
+                width,
+                conversion,
+                time_format,
+            } => {
+                let value = field(entry, root, *conversion, *time_format)?;
+                let pad = vec![b' '; width.saturating_sub(value.len())];
+                if *left {
+                    out.extend(value);
+                    out.extend(pad);
+                } else {
+                    out.extend(pad);
+                    out.extend(value);
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+// --------------------------------------------------
+fn field(
+    entry: &dyn Entry,
+    root: &Path,
+    conversion: char,
+    time_format: Option<char>,
+) -> Result<Vec<u8>> {
+    let bytes = |path: &Path| path.as_os_str().as_bytes().to_vec();
+    let path = entry.path();
+    let text = match conversion {
+        'p' => return Ok(bytes(path)),
+        'f' => return Ok(entry.file_name().as_bytes().to_vec()),
+        'h' => {
+            return Ok(path
+                .parent()
+                .filter(|dir| !dir.as_os_str().is_empty())
+                .map_or(b".".to_vec(), bytes))
+        }
+        'P' => return Ok(path.strip_prefix(root).map_or(vec![], bytes)),
+        'H' => return Ok(bytes(root)),
+        'l' => return Ok(fs::read_link(path).map_or(vec![], |t| bytes(&t))),
+        // Counted from the root since a broken link found with -L is
+        // walked on its own
+        'd' => path
+            .strip_prefix(root)
+            .map_or(0, |rel| rel.components().count())
+            .to_string(),
+        'y' => type_char(entry.file_type()).to_string(),
+        _ => {
+            let meta = entry.metadata()?;
+            match conversion {
+                's' => meta.len().to_string(),
+                'm' => format!("{:o}", meta.mode() & 0o7777),
+                'M' => format_mode(entry.file_type(), meta.mode()),
+                'n' => meta.nlink().to_string(),
+                'i' => meta.ino().to_string(),
+                'U' => meta.uid().to_string(),
+                'G' => meta.gid().to_string(),
+                'u' => get_user_by_uid(meta.uid()).map_or_else(
+                    || meta.uid().to_string(),
+                    |user| user.name().to_string_lossy().into_owned(),
+                ),
+                'g' => get_group_by_gid(meta.gid()).map_or_else(
+                    || meta.gid().to_string(),
+                    |group| group.name().to_string_lossy().into_owned(),
+                ),
+                'a' => format_time(meta.accessed()?, 'c'),
+                't' => format_time(meta.modified()?, 'c'),
+                'c' => format_time(changed(&meta)?, 'c'),
+                'A' | 'C' | 'T' => {
+                    let time = match conversion {
+                        'A' => meta.accessed()?,
+                        'C' => changed(&meta)?,
+                        _ => meta.modified()?,
+                    };
+                    let time_format = time_format
+                        .ok_or_else(|| anyhow!("missing time format"))?;
+                    format_time(time, time_format)
+                }
+                _ => bail!("unknown directive '%{conversion}'"),
+            }
+        }
+    };
+    Ok(text.into_bytes())
+}
+
+// --------------------------------------------------
+fn changed(meta: &fs::Metadata) -> Result<SystemTime> {
+    let since_epoch = Duration::new(
+        u64::try_from(meta.ctime())?,
+        u32::try_from(meta.ctime_nsec())?,
+    );
+    Ok(UNIX_EPOCH + since_epoch)
+}
+
+// --------------------------------------------------
+// Formats a time like find, where 'c' is the ctime(3) style used by %t and
+// seconds have ten fractional digits
+fn format_time(time: SystemTime, time_format: char) -> String {
+    let fmt = match time_format {
+        '@' => {
+            let since_epoch =
+                time.duration_since(UNIX_EPOCH).unwrap_or_default();
+            return format!(
+                "{}.{:09}0",
+                since_epoch.as_secs(),
+                since_epoch.subsec_nanos()
+            );
+        }
+        'c' => "%a %b %e %H:%M:%S%.9f0 %Y".to_string(),
+        '+' => "%Y-%m-%d+%H:%M:%S%.9f0".to_string(),
+        'S' => "%S%.9f0".to_string(),
+        'T' => "%H:%M:%S%.9f0".to_string(),
+        k => format!("%{k}"),
+    };
+
+    let time: DateTime<Local> = DateTime::from(time);
+    time.format(&fmt).to_string()
+}
+
+// --------------------------------------------------
+fn type_char(file_type: FileType) -> char {
+    if file_type.is_dir() {
+        'd'
+    } else if file_type.is_symlink() {
+        'l'
+    } else if file_type.is_fifo() {
+        'p'
+    } else if file_type.is_socket() {
+        's'
+    } else if file_type.is_char_device() {
+        'c'
+    } else if file_type.is_block_device() {
+        'b'
+    } else {
+        'f'
+    }
+}
+
+// --------------------------------------------------
+// Like "drwxr-xr-x" from ls -l
+fn format_mode(file_type: FileType, mode: u32) -> String {
+    let mut text = match type_char(file_type) {
+        'f' => '-',
+        c => c,
+    }
+    .to_string();
+
+    for (i, c) in "rwxrwxrwx".chars().enumerate() {
+        text.push(if mode & (0o400 >> i) == 0 { '-' } else { c });
+    }
+    text
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{format, format_mode, parse_format, Piece};
+    use std::{fs, path::Path};
+    use walkdir::WalkDir;
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(
+            parse_format("%p\\n").unwrap(),
+            [
+                Piece::Field {
+                    left: false,
+                    width: 0,
+                    conversion: 'p',
+                    time_format: None
+                },
+                Piece::Text("\n".to_string())
+            ]
+        );
+
+        assert_eq!(
+            parse_format("100%% %-10f|%TY").unwrap(),
+            [
+                Piece::Text("100% ".to_string()),
+                Piece::Field {
+                    left: true,
+                    width: 10,
+                    conversion: 'f',
+                    time_format: None
+                },
+                Piece::Text("|".to_string()),
+                Piece::Field {
+                    left: false,
+                    width: 0,
+                    conversion: 'T',
+                    time_format: Some('Y')
+                },
+            ]
+        );
+
+        let err = |fmt: &str| parse_format(fmt).unwrap_err().to_string();
+        assert_eq!(err("%z"), "unknown directive '%z'");
+        assert_eq!(err("%T"), "format ends with '%T'");
+        assert_eq!(err("%Tq"), "unknown time format '%Tq'");
+        assert_eq!(err("abc%"), "format ends with '%'");
+        assert_eq!(err("\\q"), "unknown escape '\\q'");
+    }
+
+    #[test]
+    fn test_format() {
+        let root = Path::new("tests/inputs");
+        let entry = WalkDir::new(root)
+            .into_iter()
+            .filter_map(Result::ok)
+            .find(|entry| entry.file_name() == "b.csv" && entry.depth() == 3)
+            .unwrap();
+
+        let run = |fmt: &str| {
+            let pieces = parse_format(fmt).unwrap();
+            String::from_utf8(format(&pieces, &entry, root).unwrap()).unwrap()
+        };
+
+        assert_eq!(run("%p"), "tests/inputs/a/b/b.csv");
+        assert_eq!(
+            run("%f|%h|%P|%H"),
+            "b.csv|tests/inputs/a/b|a/b/b.csv|tests/inputs"
+        );
+        assert_eq!(run("%d %y %s\\n"), "3 f 2\n");
+        assert_eq!(run("[%5s][%-5s]"), "[    2][2    ]");
+
+        let mode = fs::metadata("tests/inputs/a/b/b.csv").unwrap();
+        assert_eq!(
+            run("%m"),
+            format!(
+                "{:o}",
+                std::os::unix::fs::MetadataExt::mode(&mode) & 0o7777
+            )
+        );
+    }
+
+    #[test]
+    fn test_format_mode() {
+        let file_type = fs::metadata("Cargo.toml").unwrap().file_type();
+        assert_eq!(format_mode(file_type, 0o644), "-rw-r--r--");
+        let dir_type = fs::metadata("src").unwrap().file_type();
+        assert_eq!(format_mode(dir_type, 0o751), "drwxr-x--x");
+    }
+}
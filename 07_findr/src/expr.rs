@@ -0,0 +1,839 @@
+use crate::{
+    action::{parse_format, Action, Context, Exec},
+    walk::Entry,
+};
+use anyhow::{anyhow, bail, Result};
+use clap::{builder::PossibleValue, ValueEnum};
+use globset::{GlobBuilder, GlobMatcher};
+use regex::Regex;
+use std::{
+    fs, iter::Peekable, os::unix::fs::MetadataExt, slice, time::SystemTime,
+};
+use users::{get_group_by_name, get_user_by_name};
+
+// A predicate tree built from find-style tokens like
+// `( -name a -or -name b ) -and -not -type l`
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq))]
+pub enum Expr {
+    Test(Test),
+    Action(Action),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn eval(
+        &mut self,
+        entry: &dyn Entry,
+        ctx: &mut Context,
+    ) -> Result<bool> {
+        Ok(match self {
+            Expr::Test(test) => test.matches(entry),
+            Expr::Action(action) => action.run(entry, ctx)?,
+            Expr::Not(expr) => !expr.eval(entry, ctx)?,
+            Expr::And(left, right) => {
+                left.eval(entry, ctx)? && right.eval(entry, ctx)?
+            }
+            Expr::Or(left, right) => {
+                left.eval(entry, ctx)? || right.eval(entry, ctx)?
+            }
+        })
+    }
+
+    // The result when the tests alone decide it without running an action
+    pub fn decide(&self, entry: &dyn Entry) -> Option<bool> {
+        match self {
+            Expr::Test(test) => Some(test.matches(entry)),
+            Expr::Action(_) => None,
+            Expr::Not(expr) => expr.decide(entry).map(|result| !result),
+            Expr::And(left, right) => match left.decide(entry)? {
+                true => right.decide(entry),
+                false => Some(false),
+            },
+            Expr::Or(left, right) => match left.decide(entry)? {
+                true => Some(true),
+                false => right.decide(entry),
+            },
+        }
+    }
+
+    // The actions in the tree from left to right
+    pub fn actions(&mut self) -> Vec<&mut Action> {
+        match self {
+            Expr::Test(_) => vec![],
+            Expr::Action(action) => vec![action],
+            Expr::Not(expr) => expr.actions(),
+            Expr::And(left, right) | Expr::Or(left, right) => {
+                let mut actions = left.actions();
+                actions.extend(right.actions());
+                actions
+            }
+        }
+    }
+
+    // Joins expressions with -and, which is true for no expressions
+    pub fn all(exprs: impl IntoIterator<Item = Expr>) -> Expr {
+        exprs
+            .into_iter()
+            .reduce(|left, right| Expr::And(Box::new(left), Box::new(right)))
+            .unwrap_or(Expr::Test(Test::True))
+    }
+
+    // Joins expressions with -or, which is false for no expressions
+    pub fn any(exprs: impl IntoIterator<Item = Expr>) -> Expr {
+        exprs
+            .into_iter()
+            .reduce(|left, right| Expr::Or(Box::new(left), Box::new(right)))
+            .unwrap_or(Expr::Test(Test::False))
+    }
+}
+
+#[derive(Debug)]
+pub enum Test {
+    True,
+    False,
+    Name(GlobMatcher),
+    Path(GlobMatcher),
+    Regex(Regex),
+    Type(Vec<EntryType>),
+    Size(Size),
+    Mtime { days: Numeric, now: SystemTime },
+    Newer(SystemTime),
+    Perm(Perm),
+    User(u32),
+    Group(u32),
+    Empty,
+}
+
+impl Test {
+    pub fn matches(&self, entry: &dyn Entry) -> bool {
+        match self {
+            Test::True => true,
+            Test::False => false,
+            Test::Name(glob) => glob.is_match(entry.file_name()),
+            Test::Path(glob) => glob.is_match(entry.path()),
+            Test::Regex(re) => re.is_match(&entry.path().to_string_lossy()),
+            Test::Type(entry_types) => {
+                entry_types.iter().any(|entry_type| match entry_type {
+                    EntryType::Link => entry.file_type().is_symlink(),
+                    EntryType::Dir => entry.file_type().is_dir(),
+                    EntryType::File => entry.file_type().is_file(),
+                })
+            }
+            Test::Size(size) => entry.metadata().is_ok_and(|meta| {
+                size.num.matches(meta.len().div_ceil(size.unit))
+            }),
+            Test::Mtime { days, now } => entry.metadata().is_ok_and(|meta| {
+                let age = meta
+                    .modified()
+                    .ok()
+                    .and_then(|modified| now.duration_since(modified).ok())
+                    .map_or(0, |age| age.as_secs() / 86400);
+                days.matches(age)
+            }),
+            Test::Newer(newer) => entry
+                .metadata()
+                .ok()
+                .and_then(|meta| meta.modified().ok())
+                .is_some_and(|modified| modified > *newer),
+            Test::Perm(perm) => {
+                entry.metadata().is_ok_and(|meta| perm.matches(meta.mode()))
+            }
+            Test::User(uid) => {
+                entry.metadata().is_ok_and(|m| m.uid() == *uid)
+            }
+            Test::Group(gid) => {
+                entry.metadata().is_ok_and(|m| m.gid() == *gid)
+            }
+            Test::Empty => entry.metadata().is_ok_and(|meta| {
+                if meta.is_dir() {
+                    fs::read_dir(entry.path())
+                        .is_ok_and(|mut dir| dir.next().is_none())
+                } else {
+                    meta.is_file() && meta.len() == 0
+                }
+            }),
+        }
+    }
+}
+
+// Matchers have no equality, so tests compare their patterns
+#[cfg(test)]
+impl PartialEq for Test {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Test::Name(g1), Test::Name(g2))
+            | (Test::Path(g1), Test::Path(g2)) => g1.glob() == g2.glob(),
+            (Test::Regex(re1), Test::Regex(re2)) => {
+                re1.as_str() == re2.as_str()
+            }
+            _ => format!("{self:?}") == format!("{other:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum EntryType {
+    Dir,
+    File,
+    Link,
+}
+
+impl ValueEnum for EntryType {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[EntryType::Dir, EntryType::File, EntryType::Link]
+    }
+
+    fn to_possible_value<'a>(&self) -> Option<PossibleValue> {
+        Some(match self {
+            EntryType::Dir => PossibleValue::new("d"),
+            EntryType::File => PossibleValue::new("f"),
+            EntryType::Link => PossibleValue::new("l"),
+        })
+    }
+}
+
+// A number that find compares as less than (-N), exactly (N) or more than
+// (+N)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Numeric {
+    Less(u64),
+    Exactly(u64),
+    More(u64),
+}
+
+impl Numeric {
+    pub fn matches(&self, val: u64) -> bool {
+        match *self {
+            Numeric::Less(num) => val < num,
+            Numeric::Exactly(num) => val == num,
+            Numeric::More(num) => val > num,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Size {
+    pub num: Numeric,
+    pub unit: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Perm {
+    Exactly(u32),
+    All(u32),
+    Any(u32),
+}
+
+impl Perm {
+    pub fn matches(&self, mode: u32) -> bool {
+        let mode = mode & 0o7777;
+        match *self {
+            Perm::Exactly(bits) => mode == bits,
+            Perm::All(bits) => mode & bits == bits,
+            Perm::Any(bits) => bits == 0 || mode & bits != 0,
+        }
+    }
+}
+
+// Global options like -maxdepth that apply to the whole walk wherever they
+// are in the expression
+#[derive(Debug, Default, PartialEq)]
+pub struct Options {
+    pub mindepth: Option<usize>,
+    pub maxdepth: Option<usize>,
+    pub xdev: bool,
+}
+
+type Tokens<'a> = Peekable<slice::Iter<'a, String>>;
+
+// --------------------------------------------------
+// expr := and { ( -o | -or ) and }
+// and  := not { [ -a | -and ] not }
+// not  := ( ! | -not ) not | primary
+// primary := "(" expr ")" | test
+pub fn parse(tokens: &[String]) -> Result<(Expr, Options)> {
+    let mut tokens = tokens.iter().peekable();
+    let mut options = Options::default();
+    let expr = parse_or(&mut tokens, &mut options)?;
+    match tokens.next() {
+        Some(token) if token == ")" => {
+            bail!("invalid expression; too many ')'")
+        }
+        Some(token) => bail!("invalid expression; unexpected '{token}'"),
+        _ => Ok((expr, options)),
+    }
+}
+
+// --------------------------------------------------
+fn parse_or(tokens: &mut Tokens, options: &mut Options) -> Result<Expr> {
+    let mut expr = parse_and(tokens, options)?;
+    while let Some(op) =
+        tokens.next_if(|t| matches!(t.as_str(), "-o" | "-or"))
+    {
+        expect_operand(tokens, op)?;
+        expr =
+            Expr::Or(Box::new(expr), Box::new(parse_and(tokens, options)?));
+    }
+    Ok(expr)
+}
+
+// --------------------------------------------------
+fn parse_and(tokens: &mut Tokens, options: &mut Options) -> Result<Expr> {
+    let mut expr = parse_not(tokens, options)?;
+    loop {
+        // Adjacent expressions are implicitly joined with -and
+        match tokens.peek().map(|t| t.as_str()) {
+            None | Some(")" | "-o" | "-or") => break,
+            Some("-a" | "-and") => {
+                let op =
+                    tokens.next().map(String::as_str).unwrap_or_default();
+                expect_operand(tokens, op)?;
+            }
+            _ => {}
+        }
+        expr =
+            Expr::And(Box::new(expr), Box::new(parse_not(tokens, options)?));
+    }
+    Ok(expr)
+}
+
+// --------------------------------------------------
+fn parse_not(tokens: &mut Tokens, options: &mut Options) -> Result<Expr> {
+    match tokens.next_if(|t| matches!(t.as_str(), "!" | "-not")) {
+        Some(op) => {
+            expect_operand(tokens, op)?;
+            Ok(Expr::Not(Box::new(parse_not(tokens, options)?)))
+        }
+        _ => parse_primary(tokens, options),
+    }
+}
+
+// --------------------------------------------------
+fn parse_primary(tokens: &mut Tokens, options: &mut Options) -> Result<Expr> {
+    let token = match tokens.next() {
+        Some(token) => token.as_str(),
+        _ => bail!("invalid expression; expected a test"),
+    };
+
+    match token {
+        "(" => {
+            if tokens.peek().is_some_and(|t| *t == ")") {
+                bail!(
+                    "invalid expression; empty parentheses are not allowed"
+                );
+            }
+            let expr = parse_or(tokens, options)?;
+            match tokens.next() {
+                Some(t) if t == ")" => Ok(expr),
+                _ => bail!(
+                    "invalid expression; expected ')' but did not find one"
+                ),
+            }
+        }
+        ")" => bail!("invalid expression; too many ')'"),
+        "-o" | "-or" | "-a" | "-and" => {
+            bail!("invalid expression; '{token}' has nothing before it")
+        }
+        "-print" => Ok(Expr::Action(Action::Print)),
+        "-print0" => Ok(Expr::Action(Action::Print0)),
+        "-delete" => Ok(Expr::Action(Action::Delete)),
+        "-prune" => Ok(Expr::Action(Action::Prune)),
+        "-printf" => {
+            let fmt = tokens
+                .next()
+                .ok_or_else(|| anyhow!("missing argument to '-printf'"))?;
+            let pieces = parse_format(fmt).map_err(|e| {
+                anyhow!("invalid argument '{fmt}' to '-printf': {e}")
+            })?;
+            Ok(Expr::Action(Action::Printf(pieces)))
+        }
+        "-exec" | "-execdir" => parse_exec(token, tokens),
+        // Global options are always true
+        "-mindepth" => {
+            options.mindepth = Some(parse_levels(token, tokens)?);
+            Ok(Expr::Test(Test::True))
+        }
+        "-maxdepth" => {
+            options.maxdepth = Some(parse_levels(token, tokens)?);
+            Ok(Expr::Test(Test::True))
+        }
+        "-xdev" => {
+            options.xdev = true;
+            Ok(Expr::Test(Test::True))
+        }
+        _ => Ok(Expr::Test(parse_test(token, tokens)?)),
+    }
+}
+
+// --------------------------------------------------
+// The command runs once per entry when ended with ";" or with as many
+// entries as possible when ended with "{} +"
+fn parse_exec(name: &str, tokens: &mut Tokens) -> Result<Expr> {
+    let mut command: Vec<String> = vec![];
+    let batch = loop {
+        match tokens.next().map(String::as_str) {
+            Some(";") => break false,
+            Some("+") if command.last().is_some_and(|arg| arg == "{}") => {
+                break true
+            }
+            Some(arg) => command.push(arg.to_string()),
+            _ => bail!("missing argument to '{name}'"),
+        }
+    };
+
+    let exec = Exec::new(command, batch, name == "-execdir")
+        .map_err(|e| anyhow!("invalid argument to '{name}': {e}"))?;
+    Ok(Expr::Action(Action::Exec(exec)))
+}
+
+// --------------------------------------------------
+fn parse_levels(name: &str, tokens: &mut Tokens) -> Result<usize> {
+    let val = tokens
+        .next()
+        .ok_or_else(|| anyhow!("missing argument to '{name}'"))?;
+    val.parse()
+        .map_err(|e| anyhow!("invalid argument '{val}' to '{name}': {e}"))
+}
+
+// --------------------------------------------------
+fn expect_operand(tokens: &mut Tokens, op: &str) -> Result<()> {
+    match tokens.peek() {
+        Some(token) if *token != ")" => Ok(()),
+        _ => bail!("invalid expression; '{op}' has nothing after it"),
+    }
+}
+
+// --------------------------------------------------
+fn parse_test(name: &str, tokens: &mut Tokens) -> Result<Test> {
+    let mut arg = |parse: &dyn Fn(&str) -> Result<Test>| -> Result<Test> {
+        let val = tokens
+            .next()
+            .ok_or_else(|| anyhow!("missing argument to '{name}'"))?;
+        parse(val)
+            .map_err(|e| anyhow!("invalid argument '{val}' to '{name}': {e}"))
+    };
+
+    match name {
+        "-true" => Ok(Test::True),
+        "-false" => Ok(Test::False),
+        "-empty" => Ok(Test::Empty),
+        "-name" => arg(&|val| parse_glob(val, false).map(Test::Name)),
+        "-iname" => arg(&|val| parse_glob(val, true).map(Test::Name)),
+        "-path" => arg(&|val| parse_glob(val, false).map(Test::Path)),
+        "-ipath" => arg(&|val| parse_glob(val, true).map(Test::Path)),
+        "-regex" => arg(&|val| parse_regex(val).map(Test::Regex)),
+        "-type" => arg(&|val| {
+            val.split(',')
+                .map(|t| {
+                    EntryType::from_str(t, false).map_err(|e| anyhow!(e))
+                })
+                .collect::<Result<_>>()
+                .map(Test::Type)
+        }),
+        "-size" => arg(&|val| parse_size(val).map(Test::Size)),
+        "-mtime" => arg(&|val| {
+            Ok(Test::Mtime {
+                days: parse_numeric(val)?,
+                now: SystemTime::now(),
+            })
+        }),
+        "-newer" => arg(&|val| parse_newer(val).map(Test::Newer)),
+        "-perm" => arg(&|val| parse_perm(val).map(Test::Perm)),
+        "-user" => arg(&|val| parse_user(val).map(Test::User)),
+        "-group" => arg(&|val| parse_group(val).map(Test::Group)),
+        _ => bail!("unknown predicate '{name}'"),
+    }
+}
+
+// --------------------------------------------------
+// Shell-style patterns where "*" and "?" also match "/" and a leading "."
+pub fn parse_glob(val: &str, case_insensitive: bool) -> Result<GlobMatcher> {
+    let glob = GlobBuilder::new(val)
+        .literal_separator(false)
+        .backslash_escape(true)
+        .case_insensitive(case_insensitive)
+        .build()?;
+    Ok(glob.compile_matcher())
+}
+
+// --------------------------------------------------
+// Like find, the regex has to match the whole path
+pub fn parse_regex(val: &str) -> Result<Regex> {
+    Ok(Regex::new(&format!("^(?:{val})$"))?)
+}
+
+// --------------------------------------------------
+pub fn parse_numeric(val: &str) -> Result<Numeric> {
+    let (make, num): (fn(u64) -> Numeric, &str) = match val.strip_prefix('+')
+    {
+        Some(num) => (Numeric::More, num),
+        _ => match val.strip_prefix('-') {
+            Some(num) => (Numeric::Less, num),
+            _ => (Numeric::Exactly, val),
+        },
+    };
+
+    num.parse()
+        .map(make)
+        .map_err(|_| anyhow!("expected a number like N, +N or -N"))
+}
+
+// --------------------------------------------------
+pub fn parse_size(val: &str) -> Result<Size> {
+    let (val, unit) = match val.char_indices().last() {
+        Some((i, c)) if c.is_ascii_alphabetic() => {
+            let unit = match c {
+                'c' => 1,
+                'w' => 2,
+                'b' => 512,
+                'k' => 1024,
+                'M' => 1024 * 1024,
+                'G' => 1024 * 1024 * 1024,
+                _ => bail!("unknown unit '{c}'"),
+            };
+            (&val[..i], unit)
+        }
+        _ => (val, 512),
+    };
+
+    Ok(Size {
+        num: parse_numeric(val)?,
+        unit,
+    })
+}
+
+// --------------------------------------------------
+pub fn parse_newer(filename: &str) -> Result<SystemTime> {
+    fs::metadata(filename)
+        .and_then(|meta| meta.modified())
+        .map_err(|e| anyhow!("{filename}: {e}"))
+}
+
+// --------------------------------------------------
+pub fn parse_perm(val: &str) -> Result<Perm> {
+    let (make, mode): (fn(u32) -> Perm, &str) = match val.strip_prefix('-') {
+        Some(mode) => (Perm::All, mode),
+        _ => match val.strip_prefix('/') {
+            Some(mode) => (Perm::Any, mode),
+            _ => (Perm::Exactly, val),
+        },
+    };
+
+    match u32::from_str_radix(mode, 8) {
+        Ok(bits) if bits <= 0o7777 => Ok(make(bits)),
+        _ => bail!("expected an octal mode like 644, -111 or /111"),
+    }
+}
+
+// --------------------------------------------------
+pub fn parse_user(val: &str) -> Result<u32> {
+    get_user_by_name(val)
+        .map(|user| user.uid())
+        .or_else(|| val.parse().ok())
+        .ok_or_else(|| anyhow!("no such user"))
+}
+
+// --------------------------------------------------
+pub fn parse_group(val: &str) -> Result<u32> {
+    get_group_by_name(val)
+        .map(|group| group.gid())
+        .or_else(|| val.parse().ok())
+        .ok_or_else(|| anyhow!("no such group"))
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse, parse_glob, parse_regex, EntryType, Expr, Numeric, Options,
+        Size, Test,
+    };
+    use crate::action::{Action, Context};
+    use cliutil::Reporter;
+    use std::path::PathBuf;
+    use walkdir::WalkDir;
+
+    fn tokens(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    fn name(pattern: &str) -> Expr {
+        Expr::Test(Test::Name(parse_glob(pattern, false).unwrap()))
+    }
+
+    fn and(left: Expr, right: Expr) -> Expr {
+        Expr::And(Box::new(left), Box::new(right))
+    }
+
+    fn or(left: Expr, right: Expr) -> Expr {
+        Expr::Or(Box::new(left), Box::new(right))
+    }
+
+    fn not(expr: Expr) -> Expr {
+        Expr::Not(Box::new(expr))
+    }
+
+    fn link() -> Expr {
+        Expr::Test(Test::Type(vec![EntryType::Link]))
+    }
+
+    fn parse_err(line: &str) -> String {
+        parse(&tokens(line)).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(&tokens("-name a")).unwrap().0, name("a"));
+        assert_eq!(
+            parse(&tokens("-name a -name b")).unwrap().0,
+            and(name("a"), name("b"))
+        );
+        assert_eq!(
+            parse(&tokens("-name a -a -name b")).unwrap().0,
+            and(name("a"), name("b"))
+        );
+        assert_eq!(
+            parse(&tokens("-size +10M")).unwrap().0,
+            Expr::Test(Test::Size(Size {
+                num: Numeric::More(10),
+                unit: 1024 * 1024
+            }))
+        );
+        assert_eq!(
+            parse(&tokens("-type f,l")).unwrap().0,
+            Expr::Test(Test::Type(vec![EntryType::File, EntryType::Link]))
+        );
+    }
+
+    #[test]
+    fn test_parse_precedence() {
+        // -and binds tighter than -or
+        assert_eq!(
+            parse(&tokens("-name a -or -name b -name c")).unwrap().0,
+            or(name("a"), and(name("b"), name("c")))
+        );
+        assert_eq!(
+            parse(&tokens("-name a -name b -o -name c")).unwrap().0,
+            or(and(name("a"), name("b")), name("c"))
+        );
+
+        // -not binds tightest
+        assert_eq!(
+            parse(&tokens("! -name a -name b")).unwrap().0,
+            and(not(name("a")), name("b"))
+        );
+        assert_eq!(
+            parse(&tokens("-not -not -name a")).unwrap().0,
+            not(not(name("a")))
+        );
+
+        // Parentheses group
+        assert_eq!(
+            parse(&tokens("( -name a -or -name b ) -and -not -type l"))
+                .unwrap()
+                .0,
+            and(or(name("a"), name("b")), not(link()))
+        );
+        assert_eq!(
+            parse(&tokens("-name a -o ( ( -name b ) )")).unwrap().0,
+            or(name("a"), name("b"))
+        );
+    }
+
+    #[test]
+    fn test_parse_options() {
+        let (expr, options) =
+            parse(&tokens("-maxdepth 2 -name a -xdev -mindepth 1")).unwrap();
+        assert_eq!(
+            expr,
+            and(
+                and(
+                    and(Expr::Test(Test::True), name("a")),
+                    Expr::Test(Test::True)
+                ),
+                Expr::Test(Test::True)
+            )
+        );
+        assert_eq!(
+            options,
+            Options {
+                mindepth: Some(1),
+                maxdepth: Some(2),
+                xdev: true
+            }
+        );
+        assert_eq!(parse(&tokens("-name a")).unwrap().1, Options::default());
+        assert_eq!(parse_err("-maxdepth"), "missing argument to '-maxdepth'");
+        assert_eq!(
+            parse_err("-mindepth -1"),
+            "invalid argument '-1' to '-mindepth': invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            parse_err("( -name a"),
+            "invalid expression; expected ')' but did not find one"
+        );
+        assert_eq!(
+            parse_err("( ( -name a )"),
+            "invalid expression; expected ')' but did not find one"
+        );
+        assert_eq!(
+            parse_err("-name a )"),
+            "invalid expression; too many ')'"
+        );
+        assert_eq!(parse_err(")"), "invalid expression; too many ')'");
+        assert_eq!(
+            parse_err("( )"),
+            "invalid expression; empty parentheses are not allowed"
+        );
+        assert_eq!(
+            parse_err("-or -name a"),
+            "invalid expression; '-or' has nothing before it"
+        );
+        assert_eq!(
+            parse_err("-name a -o"),
+            "invalid expression; '-o' has nothing after it"
+        );
+        assert_eq!(
+            parse_err("( -name a -and )"),
+            "invalid expression; '-and' has nothing after it"
+        );
+        assert_eq!(
+            parse_err("-name a !"),
+            "invalid expression; '!' has nothing after it"
+        );
+        assert_eq!(parse_err("-foo"), "unknown predicate '-foo'");
+        assert_eq!(parse_err("-name"), "missing argument to '-name'");
+        assert_eq!(
+            parse_err("-size 10x"),
+            "invalid argument '10x' to '-size': unknown unit 'x'"
+        );
+        assert!(parse_err("-type x").starts_with("invalid argument 'x'"));
+    }
+
+    #[test]
+    fn test_matches() {
+        let find = |line: &str| -> Vec<String> {
+            let mut expr = parse(&tokens(line)).unwrap().0;
+            let mut reporter = Reporter::new("test");
+            let mut ctx = Context {
+                out: Box::new(vec![]),
+                reporter: &mut reporter,
+                root: PathBuf::from("tests/inputs"),
+                prune: false,
+            };
+            let mut paths: Vec<_> = WalkDir::new("tests/inputs")
+                .into_iter()
+                .filter_map(Result::ok)
+                .filter(|entry| expr.eval(entry, &mut ctx).unwrap())
+                .map(|entry| entry.path().display().to_string())
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(
+            find("( -name *.csv -or -name *.mp3 ) -and -not -type l"),
+            [
+                "tests/inputs/a/b/b.csv",
+                "tests/inputs/a/b/c/c.mp3",
+                "tests/inputs/d/e/e.mp3",
+                "tests/inputs/g.csv",
+            ]
+        );
+        assert_eq!(
+            find("-type d -name [ab] -o -type l"),
+            ["tests/inputs/a", "tests/inputs/a/b", "tests/inputs/d/b.csv"]
+        );
+        assert_eq!(
+            find("! ( -type d -o -type f )"),
+            ["tests/inputs/d/b.csv"]
+        );
+        assert!(find("-false").is_empty());
+
+        // Globs match the whole name and regexes the whole path
+        assert_eq!(find("-name .csv"), Vec::<String>::new());
+        assert_eq!(
+            find("-iname *.CSV -type f"),
+            ["tests/inputs/a/b/b.csv", "tests/inputs/g.csv"]
+        );
+        assert_eq!(
+            find("-path */d/* -name *.t??"),
+            ["tests/inputs/d/d.tsv", "tests/inputs/d/d.txt"]
+        );
+        assert_eq!(find("-ipath */E/*"), ["tests/inputs/d/e/e.mp3"]);
+        assert_eq!(find("-regex [a-z]+[.]mp3"), Vec::<String>::new());
+        assert_eq!(
+            find("-regex .*/[a-c][.][a-z0-9]+"),
+            [
+                "tests/inputs/a/a.txt",
+                "tests/inputs/a/b/b.csv",
+                "tests/inputs/a/b/c/c.mp3",
+                "tests/inputs/d/b.csv",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_patterns() {
+        assert!(parse_glob("*.csv", false).unwrap().is_match("a.csv"));
+        assert!(!parse_glob("*.csv", false).unwrap().is_match("a.CSV"));
+        assert!(parse_glob("*.csv", true).unwrap().is_match("a.CSV"));
+        assert!(parse_glob("*", false).unwrap().is_match(".hidden"));
+        assert!(parse_glob("a*c", false).unwrap().is_match("a/b/c"));
+        assert!(parse_glob(r"\*", false).unwrap().is_match("*"));
+        assert!(!parse_glob(r"\*", false).unwrap().is_match("a"));
+        assert!(parse_glob("[a", false).is_err());
+
+        assert!(parse_regex("a|b").unwrap().is_match("b"));
+        assert!(!parse_regex("a|b").unwrap().is_match("ab"));
+        assert!(!parse_regex("csv").unwrap().is_match("xcsv"));
+        assert!(parse_regex("(").is_err());
+        assert!(parse_err("-name [a").starts_with("invalid argument '[a'"));
+    }
+
+    #[test]
+    fn test_parse_actions() {
+        assert_eq!(
+            parse(&tokens("-name a -print0 -o -print")).unwrap().0,
+            or(
+                and(name("a"), Expr::Action(Action::Print0)),
+                Expr::Action(Action::Print)
+            )
+        );
+
+        let mut expr = parse(&tokens("-exec echo {} ; -delete")).unwrap().0;
+        assert_eq!(expr.actions().len(), 2);
+        assert!(matches!(expr.actions()[1], Action::Delete));
+
+        assert!(parse(&tokens("-exec echo {} + -print")).is_ok());
+        assert_eq!(
+            parse(&tokens("-name target -prune -o -print")).unwrap().0,
+            or(
+                and(name("target"), Expr::Action(Action::Prune)),
+                Expr::Action(Action::Print)
+            )
+        );
+        assert!(parse(&tokens("-execdir wc -l {} ;")).is_ok());
+        assert_eq!(parse_err("-exec echo {}"), "missing argument to '-exec'");
+        assert_eq!(
+            parse_err("-exec ;"),
+            "invalid argument to '-exec': missing command"
+        );
+        assert_eq!(
+            parse_err("-exec {} {} +"),
+            "invalid argument to '-exec': only one instance of {} is \
+            supported with ... +"
+        );
+        assert_eq!(
+            parse_err("-printf %z"),
+            "invalid argument '%z' to '-printf': unknown directive '%z'"
+        );
+    }
+}
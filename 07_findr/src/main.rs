@@ -1,7 +1,23 @@
+mod action;
+mod expr;
+mod walk;
+
+use action::{Action, Context};
 use anyhow::Result;
-use clap::{builder::PossibleValue, ArgAction, Parser, ValueEnum};
-use regex::Regex;
-use walkdir::{DirEntry, WalkDir};
+use clap::{ArgAction, Parser};
+use cliutil::Reporter;
+use expr::{
+    parse_glob, parse_group, parse_newer, parse_numeric, parse_perm,
+    parse_size, parse_user, EntryType, Expr, Numeric, Options, Perm, Size,
+    Test,
+};
+use globset::GlobMatcher;
+use std::{
+    io::{self, BufWriter},
+    path::PathBuf,
+    time::SystemTime,
+};
+use walk::Walk;
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
@@ -11,16 +27,26 @@ struct Args {
     #[arg(value_name = "PATH", default_value = ".")]
     paths: Vec<String>,
 
-    /// Names
+    /// Names matching shell patterns like *.csv
     #[arg(
         short('n'),
         long("name"),
         value_name = "NAME",
-        value_parser(Regex::new),
+        value_parser(|val: &str| parse_glob(val, false)),
+        action(ArgAction::Append),
+        num_args(0..)
+    )]
+    names: Vec<GlobMatcher>,
+
+    /// Like --name but case-insensitive
+    #[arg(
+        long("iname"),
+        value_name = "NAME",
+        value_parser(|val: &str| parse_glob(val, true)),
         action(ArgAction::Append),
         num_args(0..)
     )]
-    names: Vec<Regex>,
+    inames: Vec<GlobMatcher>,
 
     /// Entry types
     #[arg(
@@ -32,73 +58,221 @@ struct Args {
         num_args(0..)
     )]
     entry_types: Vec<EntryType>,
-}
 
-#[derive(Debug, Eq, PartialEq, Clone)]
-enum EntryType {
-    Dir,
-    File,
-    Link,
-}
+    /// Size in units of c, w, b, k, M or G (default b), e.g., +10M
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser(parse_size),
+        allow_hyphen_values(true),
+        action(ArgAction::Append)
+    )]
+    size: Vec<Size>,
 
-impl ValueEnum for EntryType {
-    fn value_variants<'a>() -> &'a [Self] {
-        &[EntryType::Dir, EntryType::File, EntryType::Link]
-    }
+    /// Days since last modification, e.g., -7
+    #[arg(
+        long,
+        value_name = "DAYS",
+        value_parser(parse_numeric),
+        allow_hyphen_values(true),
+        action(ArgAction::Append)
+    )]
+    mtime: Vec<Numeric>,
 
-    fn to_possible_value<'a>(&self) -> Option<PossibleValue> {
-        Some(match self {
-            EntryType::Dir => PossibleValue::new("d"),
-            EntryType::File => PossibleValue::new("f"),
-            EntryType::Link => PossibleValue::new("l"),
-        })
-    }
+    /// Modified more recently than FILE
+    #[arg(long, value_name = "FILE", value_parser(parse_newer))]
+    newer: Option<SystemTime>,
+
+    /// Permission bits: exactly MODE, all of -MODE or any of /MODE
+    #[arg(
+        long,
+        value_name = "MODE",
+        value_parser(parse_perm),
+        allow_hyphen_values(true)
+    )]
+    perm: Option<Perm>,
+
+    /// Owned by user name or ID
+    #[arg(long, value_name = "USER", value_parser(parse_user))]
+    user: Option<u32>,
+
+    /// Owned by group name or ID
+    #[arg(long, value_name = "GROUP", value_parser(parse_group))]
+    group: Option<u32>,
+
+    /// Empty files and directories
+    #[arg(long)]
+    empty: bool,
+
+    /// Never follow symbolic links (default)
+    #[arg(short('P'), overrides_with_all(["follow_roots", "follow"]))]
+    no_follow: bool,
+
+    /// Follow symbolic links given as search paths
+    #[arg(short('H'), overrides_with_all(["no_follow", "follow"]))]
+    follow_roots: bool,
+
+    /// Follow all symbolic links
+    #[arg(short('L'), overrides_with_all(["no_follow", "follow_roots"]))]
+    follow: bool,
+
+    /// Don't descend into directories on other filesystems
+    #[arg(long)]
+    xdev: bool,
+
+    /// Walk the entries of each directory in name order
+    #[arg(long)]
+    sort: bool,
+
+    /// Skip hidden entries and those matched by .gitignore or .ignore files
+    #[arg(long)]
+    respect_ignore: bool,
+
+    /// Minimum depth below the search paths
+    #[arg(long, value_name = "LEVELS", default_value = "0")]
+    mindepth: usize,
+
+    /// Maximum depth below the search paths
+    #[arg(long, value_name = "LEVELS")]
+    maxdepth: Option<usize>,
+
+    /// Tests like -name, -type or -size joined with -and, -or, -not and
+    /// parentheses
+    #[arg(value_name = "EXPRESSION", last(true), allow_hyphen_values(true))]
+    expression: Vec<String>,
 }
 
 // --------------------------------------------------
 fn main() {
-    if let Err(e) = run(Args::parse()) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let args = Args::parse_from(split_expression(std::env::args()));
+    let mut reporter = Reporter::new(env!("CARGO_BIN_NAME"));
+    if let Err(e) = run(args, &mut reporter) {
+        // Like find, stop quietly once the reader of the output is gone
+        if !is_broken_pipe(&e) {
+            reporter.report(e);
+        }
     }
+    std::process::exit(reporter.exit_code());
 }
 
 // --------------------------------------------------
-fn run(args: Args) -> Result<()> {
-    let type_filter = |entry: &DirEntry| {
-        args.entry_types.is_empty()
-            || args.entry_types.iter().any(|entry_type| match entry_type {
-                EntryType::Link => entry.file_type().is_symlink(),
-                EntryType::Dir => entry.file_type().is_dir(),
-                EntryType::File => entry.file_type().is_file(),
-            })
+fn run(args: Args, reporter: &mut Reporter) -> Result<()> {
+    let mut tests = options_tests(&args);
+    let mut options = Options::default();
+    if !args.expression.is_empty() {
+        let (expr, expr_options) = expr::parse(&args.expression)?;
+        tests.push(expr);
+        options = expr_options;
+    }
+
+    // Without an action other than -prune, matching entries are printed
+    let mut expr = Expr::all(tests);
+    if expr
+        .actions()
+        .iter()
+        .all(|action| matches!(action, Action::Prune))
+    {
+        expr =
+            Expr::And(Box::new(expr), Box::new(Expr::Action(Action::Print)));
+    }
+
+    // Directories can only be deleted after their contents
+    let contents_first = expr
+        .actions()
+        .iter()
+        .any(|action| matches!(action, Action::Delete));
+
+    let mut ctx = Context {
+        out: Box::new(BufWriter::new(io::stdout())),
+        reporter,
+        root: PathBuf::new(),
+        prune: false,
     };
 
-    let name_filter = |entry: &DirEntry| {
-        args.names.is_empty()
-            || args
-                .names
-                .iter()
-                .any(|re| re.is_match(&entry.file_name().to_string_lossy()))
+    let walk = Walk {
+        follow: args.follow,
+        follow_roots: args.follow_roots,
+        xdev: args.xdev || options.xdev,
+        mindepth: options.mindepth.unwrap_or(args.mindepth),
+        maxdepth: options.maxdepth.or(args.maxdepth),
+        contents_first,
+        sort: args.sort,
+        respect_ignore: args.respect_ignore,
     };
 
     for path in &args.paths {
-        let entries = WalkDir::new(path)
-            .into_iter()
-            .filter_map(|e| match e {
-                Err(e) => {
-                    eprintln!("{e}");
-                    None
-                }
-                Ok(entry) => Some(entry),
-            })
-            .filter(type_filter)
-            .filter(name_filter)
-            .map(|entry| entry.path().display().to_string())
-            .collect::<Vec<_>>();
-
-        println!("{}", entries.join("\n"));
+        ctx.root = PathBuf::from(path);
+        walk.run(path, &mut expr, &mut ctx)?;
+    }
+
+    for action in expr.actions() {
+        action.finish(&mut ctx)?;
     }
+    ctx.out.flush()?;
 
     Ok(())
 }
+
+// --------------------------------------------------
+fn is_broken_pipe(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<io::Error>()
+        .is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe)
+}
+
+// --------------------------------------------------
+// Marks where a find-style expression like "-name a -o ( -type l )"
+// starts so that clap leaves the rest of the arguments to the expression
+// parser
+fn split_expression(args: impl Iterator<Item = String>) -> Vec<String> {
+    let mut args: Vec<String> = args.collect();
+    let start = args.iter().skip(1).position(|arg| {
+        arg == "--"
+            || matches!(arg.as_str(), "(" | ")" | "!")
+            || (arg.len() > 2
+                && arg.starts_with('-')
+                && arg[1..].starts_with(|c: char| c.is_ascii_lowercase())
+                && arg[1..].chars().all(|c| c.is_ascii_alphanumeric()))
+    });
+
+    if let Some(start) = start.map(|i| i + 1) {
+        if args[start] != "--" {
+            args.insert(start, "--".to_string());
+        }
+    }
+    args
+}
+
+// --------------------------------------------------
+// The tests given as options: names and types are joined with -or and
+// everything else with -and
+fn options_tests(args: &Args) -> Vec<Expr> {
+    let mut tests = vec![];
+    if !args.names.is_empty() || !args.inames.is_empty() {
+        tests.push(Expr::any(
+            args.names
+                .iter()
+                .chain(&args.inames)
+                .map(|glob| Expr::Test(Test::Name(glob.clone()))),
+        ));
+    }
+
+    if !args.entry_types.is_empty() {
+        tests.push(Expr::Test(Test::Type(args.entry_types.clone())));
+    }
+
+    let now = SystemTime::now();
+    tests.extend(args.size.iter().map(|&size| Expr::Test(Test::Size(size))));
+    tests.extend(
+        args.mtime
+            .iter()
+            .map(|&days| Expr::Test(Test::Mtime { days, now })),
+    );
+    tests.extend(args.newer.map(|newer| Expr::Test(Test::Newer(newer))));
+    tests.extend(args.perm.map(|perm| Expr::Test(Test::Perm(perm))));
+    tests.extend(args.user.map(|uid| Expr::Test(Test::User(uid))));
+    tests.extend(args.group.map(|gid| Expr::Test(Test::Group(gid))));
+    if args.empty {
+        tests.push(Expr::Test(Test::Empty));
+    }
+    tests
+}
@@ -0,0 +1,459 @@
+use crate::{action::Context, expr::Expr};
+use anyhow::Result;
+use cliutil::Reporter;
+use ignore::{WalkBuilder, WalkState};
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    fmt::Display,
+    fs::{self, FileType, Metadata},
+    io,
+    path::Path,
+    sync::{Arc, Mutex, RwLock},
+};
+use walkdir::{DirEntry, WalkDir};
+
+// --------------------------------------------------
+// What tests and actions need from an entry found by either walker
+pub trait Entry {
+    fn path(&self) -> &Path;
+    fn file_name(&self) -> &OsStr;
+    fn file_type(&self) -> FileType;
+    fn metadata(&self) -> io::Result<Metadata>;
+    fn depth(&self) -> usize;
+    fn path_is_symlink(&self) -> bool;
+}
+
+impl Entry for DirEntry {
+    fn path(&self) -> &Path {
+        DirEntry::path(self)
+    }
+
+    fn file_name(&self) -> &OsStr {
+        DirEntry::file_name(self)
+    }
+
+    fn file_type(&self) -> FileType {
+        DirEntry::file_type(self)
+    }
+
+    fn metadata(&self) -> io::Result<Metadata> {
+        Ok(DirEntry::metadata(self)?)
+    }
+
+    fn depth(&self) -> usize {
+        DirEntry::depth(self)
+    }
+
+    fn path_is_symlink(&self) -> bool {
+        DirEntry::path_is_symlink(self)
+    }
+}
+
+impl Entry for ignore::DirEntry {
+    fn path(&self) -> &Path {
+        ignore::DirEntry::path(self)
+    }
+
+    fn file_name(&self) -> &OsStr {
+        ignore::DirEntry::file_name(self)
+    }
+
+    // Only stdin has no type, and "-" is only walked by walkdir
+    fn file_type(&self) -> FileType {
+        ignore::DirEntry::file_type(self).expect("entry without a type")
+    }
+
+    fn metadata(&self) -> io::Result<Metadata> {
+        ignore::DirEntry::metadata(self).map_err(|e| {
+            e.into_io_error()
+                .unwrap_or_else(|| io::Error::other("no metadata"))
+        })
+    }
+
+    fn depth(&self) -> usize {
+        ignore::DirEntry::depth(self)
+    }
+
+    fn path_is_symlink(&self) -> bool {
+        ignore::DirEntry::path_is_symlink(self)
+    }
+}
+
+// --------------------------------------------------
+// What a walker found: an entry or an error about one
+enum Found<'a> {
+    Entry(&'a dyn Entry),
+    Error(WalkError<'a>),
+}
+
+// What find needs to know to report an error from either walker
+struct WalkError<'a> {
+    path: Option<&'a Path>,
+    depth: Option<usize>,
+    io_error: Option<&'a io::Error>,
+    // A path that leads back to one of its ancestors and the ancestor
+    looped: Option<(&'a Path, &'a Path)>,
+    err: &'a dyn Display,
+}
+
+impl<'a> From<&'a walkdir::Error> for WalkError<'a> {
+    fn from(e: &'a walkdir::Error) -> Self {
+        WalkError {
+            path: e.path(),
+            depth: Some(e.depth()),
+            io_error: e.io_error(),
+            looped: e
+                .loop_ancestor()
+                .map(|ancestor| (e.path().unwrap_or(ancestor), ancestor)),
+            err: e,
+        }
+    }
+}
+
+impl<'a> From<&'a ignore::Error> for WalkError<'a> {
+    fn from(e: &'a ignore::Error) -> Self {
+        WalkError {
+            path: error_path(e),
+            depth: e.depth(),
+            io_error: io_error(e),
+            looped: loop_paths(e),
+            err: e,
+        }
+    }
+}
+
+// --------------------------------------------------
+fn found<'a, E: Entry, R>(result: &'a Result<E, R>) -> Found<'a>
+where
+    WalkError<'a>: From<&'a R>,
+{
+    match result {
+        Ok(entry) => Found::Entry(entry),
+        Err(e) => Found::Error(e.into()),
+    }
+}
+
+// --------------------------------------------------
+// How to walk each search path
+pub struct Walk {
+    pub follow: bool,
+    pub follow_roots: bool,
+    pub xdev: bool,
+    pub mindepth: usize,
+    pub maxdepth: Option<usize>,
+    pub contents_first: bool,
+    pub sort: bool,
+    pub respect_ignore: bool,
+}
+
+impl Walk {
+    // Evaluates the expression for each entry as soon as it's found
+    pub fn run(
+        &self,
+        path: &str,
+        expr: &mut Expr,
+        ctx: &mut Context,
+    ) -> Result<()> {
+        // walkdir follows links the way find does, but only ignore reads
+        // ignore files. ignore also follows a link given as a search path
+        // and reads "-" as stdin.
+        let is_link = fs::symlink_metadata(path)
+            .is_ok_and(|meta| meta.file_type().is_symlink());
+        let links = self.follow || self.follow_roots;
+        if path == "-"
+            || (is_link && !links)
+            || (!self.respect_ignore && (self.contents_first || links))
+        {
+            self.walk(path, expr, ctx)
+        } else if self.sort || self.contents_first || links {
+            self.walk_ignore(path, expr, ctx)
+        } else {
+            self.walk_parallel(path, expr, ctx)
+        }
+    }
+
+    // --------------------------------------------------
+    fn walk(
+        &self,
+        path: &str,
+        expr: &mut Expr,
+        ctx: &mut Context,
+    ) -> Result<()> {
+        let mut walker = WalkDir::new(path)
+            .follow_links(self.follow)
+            .follow_root_links(self.follow || self.follow_roots)
+            .same_file_system(self.xdev)
+            .max_depth(self.maxdepth.unwrap_or(usize::MAX))
+            .contents_first(self.contents_first);
+        if self.sort {
+            walker = walker.sort_by_file_name();
+        }
+
+        // Skipping a directory only works before its contents are walked
+        let mut entries = walker.into_iter();
+        while let Some(result) = entries.next() {
+            if self.visit(found(&result), expr, ctx)? && !self.contents_first
+            {
+                entries.skip_current_dir();
+            }
+        }
+
+        Ok(())
+    }
+
+    // --------------------------------------------------
+    // One entry at a time for --sort or to respect ignore files along with
+    // -delete or link following
+    fn walk_ignore(
+        &self,
+        path: &str,
+        expr: &mut Expr,
+        ctx: &mut Context,
+    ) -> Result<()> {
+        // The walker can't be told to skip a directory once it's found, so
+        // the contents of a pruned directory are filtered out instead
+        let pruned = Arc::new(Mutex::new(HashSet::new()));
+        let mut builder = self.builder(path);
+        builder.follow_links(self.follow);
+        if self.sort {
+            builder.sort_by_file_name(|a, b| a.cmp(b));
+        }
+        let skips = Arc::clone(&pruned);
+        builder.filter_entry(move |entry| {
+            entry
+                .path()
+                .parent()
+                .is_none_or(|dir| !skips.lock().unwrap().contains(dir))
+        });
+
+        // With -delete, directories wait here until their contents are done
+        let mut dirs: Vec<ignore::DirEntry> = vec![];
+        for result in builder.build() {
+            if let (true, Ok(entry)) = (self.contents_first, &result) {
+                while dirs
+                    .last()
+                    .is_some_and(|dir| dir.depth() >= entry.depth())
+                {
+                    let dir = dirs.pop().unwrap();
+                    self.visit(Found::Entry(&dir), expr, ctx)?;
+                }
+                if entry.file_type().is_some_and(|t| t.is_dir()) {
+                    dirs.extend(result.ok());
+                    continue;
+                }
+            }
+
+            if self.visit(found(&result), expr, ctx)? {
+                if let (false, Ok(entry)) = (self.contents_first, &result) {
+                    pruned.lock().unwrap().insert(entry.path().to_path_buf());
+                }
+            }
+        }
+
+        while let Some(dir) = dirs.pop() {
+            self.visit(Found::Entry(&dir), expr, ctx)?;
+        }
+
+        Ok(())
+    }
+
+    // --------------------------------------------------
+    // Directories are read and entries are tested on many threads, but the
+    // expression is evaluated for one entry at a time when an action might
+    // run, so output and -prune, -exec and -delete are never interleaved
+    fn walk_parallel(
+        &self,
+        path: &str,
+        expr: &mut Expr,
+        ctx: &mut Context,
+    ) -> Result<()> {
+        let walker = self.builder(path).build_parallel();
+        let expr = RwLock::new(expr);
+        let state = Mutex::new((ctx, None));
+        walker.run(|| {
+            Box::new(|result| {
+                let found = found(&result);
+                if let Found::Entry(entry) = found {
+                    if expr.read().unwrap().decide(entry).is_some() {
+                        return WalkState::Continue;
+                    }
+                }
+
+                let mut expr = expr.write().unwrap();
+                let (ctx, failure) = &mut *state.lock().unwrap();
+                if failure.is_some() {
+                    return WalkState::Quit;
+                }
+                match self.visit(found, &mut expr, ctx) {
+                    Ok(true) => WalkState::Skip,
+                    Ok(false) => WalkState::Continue,
+                    Err(e) => {
+                        *failure = Some(e);
+                        WalkState::Quit
+                    }
+                }
+            })
+        });
+
+        let (_, failure) = state.into_inner().unwrap();
+        failure.map_or(Ok(()), Err)
+    }
+
+    // --------------------------------------------------
+    // Everything the walkers find goes through here so that entries are
+    // evaluated and errors reported the same way. Returns whether the entry
+    // was a directory that was pruned.
+    fn visit(
+        &self,
+        found: Found,
+        expr: &mut Expr,
+        ctx: &mut Context,
+    ) -> Result<bool> {
+        let (root, link);
+        let (entry, depth): (&dyn Entry, usize) = match found {
+            // With -H, a link given as a search path is found as what it
+            // points to even though the walker reports it as a link
+            Found::Entry(entry)
+                if self.follow_roots
+                    && entry.depth() == 0
+                    && entry.path_is_symlink() =>
+            {
+                root = followed(entry.path());
+                (root.as_ref().map_or(entry, |root| root), 0)
+            }
+            Found::Entry(entry) => (entry, entry.depth()),
+            Found::Error(WalkError {
+                looped: Some((child, ancestor)),
+                ..
+            }) => {
+                ctx.reporter.report(format!(
+                    "File system loop detected; '{}' is part of \
+                    the same file system loop as '{}'.",
+                    child.display(),
+                    ancestor.display()
+                ));
+                return Ok(false);
+            }
+            Found::Error(e) => match broken_link(e.path, e.io_error) {
+                Some(found) => {
+                    link = found;
+                    let depth = e.depth.unwrap_or_else(|| {
+                        e.path
+                            .and_then(|path| {
+                                path.strip_prefix(&ctx.root).ok()
+                            })
+                            .map_or(0, |path| path.components().count())
+                    });
+                    (&link, depth)
+                }
+                None => {
+                    report(ctx.reporter, e.path, e.io_error, e.err);
+                    return Ok(false);
+                }
+            },
+        };
+
+        if depth < self.mindepth {
+            return Ok(false);
+        }
+
+        // -prune keeps the walk from descending into a directory
+        ctx.prune = false;
+        expr.eval(entry, ctx)?;
+        Ok(ctx.prune && entry.file_type().is_dir())
+    }
+
+    // --------------------------------------------------
+    // With --respect-ignore, hidden entries and those matched by .gitignore
+    // and .ignore files are skipped
+    fn builder(&self, path: &str) -> WalkBuilder {
+        let mut builder = WalkBuilder::new(path);
+        builder
+            .standard_filters(false)
+            .hidden(self.respect_ignore)
+            .ignore(self.respect_ignore)
+            .git_ignore(self.respect_ignore)
+            .require_git(false)
+            .same_file_system(self.xdev)
+            .max_depth(self.maxdepth);
+        builder
+    }
+}
+
+// --------------------------------------------------
+// Errors for a path read like find's, e.g.,
+// "missing: No such file or directory"
+fn report(
+    reporter: &mut Reporter,
+    path: Option<&Path>,
+    io_error: Option<&io::Error>,
+    e: &dyn Display,
+) {
+    match (path, io_error) {
+        (Some(path), Some(io_error)) => {
+            let text = io_error.to_string();
+            let text = text.split(" (os error").next().unwrap_or(&text);
+            reporter.report_file(&path.display().to_string(), text);
+        }
+        _ => reporter.report(e),
+    }
+}
+
+// --------------------------------------------------
+fn error_path(e: &ignore::Error) -> Option<&Path> {
+    match e {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } => error_path(err),
+        _ => None,
+    }
+}
+
+// --------------------------------------------------
+// The serial walker wraps the walkdir error, whose text names the path again
+fn io_error(e: &ignore::Error) -> Option<&io::Error> {
+    let err = e.io_error()?;
+    err.get_ref()
+        .and_then(|inner| inner.downcast_ref::<walkdir::Error>())
+        .and_then(walkdir::Error::io_error)
+        .or(Some(err))
+}
+
+// --------------------------------------------------
+fn loop_paths(e: &ignore::Error) -> Option<(&Path, &Path)> {
+    match e {
+        ignore::Error::Loop { child, ancestor } => Some((child, ancestor)),
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. } => loop_paths(err),
+        _ => None,
+    }
+}
+
+// --------------------------------------------------
+fn followed(path: &Path) -> Option<DirEntry> {
+    WalkDir::new(path)
+        .follow_links(true)
+        .into_iter()
+        .next()
+        .and_then(Result::ok)
+}
+
+// --------------------------------------------------
+// A link that can't be followed is still found, as a link, like find does
+fn broken_link(
+    path: Option<&Path>,
+    io_error: Option<&io::Error>,
+) -> Option<DirEntry> {
+    let path = path?;
+    let is_link = fs::symlink_metadata(path)
+        .is_ok_and(|meta| meta.file_type().is_symlink());
+    if !is_link || io_error?.kind() != io::ErrorKind::NotFound {
+        return None;
+    }
+
+    WalkDir::new(path)
+        .follow_root_links(false)
+        .into_iter()
+        .next()?
+        .ok()
+}
@@ -1,9 +1,18 @@
 use anyhow::Result;
-use assert_cmd::Command;
+use assert_cmd::{cargo::cargo_bin, Command};
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
 use rand::{distributions::Alphanumeric, Rng};
-use std::{borrow::Cow, fs, path::Path};
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    fs::{self, File},
+    os::unix::fs::{symlink, MetadataExt, PermissionsExt},
+    process::{self, Stdio},
+    time::{Duration, SystemTime},
+};
+use tempfile::TempDir;
+use Node::*;
 
 const PRG: &str = "findr";
 
@@ -26,12 +35,44 @@ fn gen_bad_file() -> String {
 #[test]
 fn skips_bad_dir() -> Result<()> {
     let bad = gen_bad_file();
-    let expected = format!("{}: .* [(]os error [23][)]", &bad);
+    let expected = format!("{PRG}: {bad}: No such file or directory\n");
     Command::cargo_bin(PRG)?
-        .arg(&bad)
+        .args([&bad, "tests/inputs/g.csv"])
+        .assert()
+        .failure()
+        .stdout("tests/inputs/g.csv\n")
+        .stderr(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn bad_dir_sorted() -> Result<()> {
+    let bad = gen_bad_file();
+    let expected = format!("{PRG}: {bad}: No such file or directory\n");
+    Command::cargo_bin(PRG)?
+        .args([&bad, "--sort"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_blank_lines() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/f", "tests/inputs/d", "-name", "*.tsv"])
         .assert()
         .success()
-        .stderr(predicate::str::is_match(expected)?);
+        .stdout("tests/inputs/d/d.tsv\n");
+
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/a", "tests/inputs/f", "-name", "nothing"])
+        .assert()
+        .success()
+        .stdout("");
     Ok(())
 }
 
@@ -39,10 +80,10 @@ fn skips_bad_dir() -> Result<()> {
 #[test]
 fn dies_bad_name() -> Result<()> {
     Command::cargo_bin(PRG)?
-        .args(["--name", "*.csv"])
+        .args(["--name", "[.csv"])
         .assert()
         .failure()
-        .stderr(predicate::str::contains("error: invalid value '*.csv'"));
+        .stderr(predicate::str::contains("error: invalid value '[.csv'"));
     Ok(())
 }
 
@@ -60,14 +101,14 @@ fn dies_bad_type() -> Result<()> {
 
 // --------------------------------------------------
 #[cfg(windows)]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Owned(format!("{}.windows", expected_file))
     format!("{}.windows", expected_file).into()
 }
 
 // --------------------------------------------------
 #[cfg(not(windows))]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Borrowed(expected_file)
     expected_file.into()
 }
@@ -79,16 +120,137 @@ fn run(args: &[&str], expected_file: &str) -> Result<()> {
     let mut expected: Vec<&str> =
         contents.split('\n').filter(|s| !s.is_empty()).collect();
     expected.sort();
+    assert_eq!(sorted_lines(args)?, expected);
+    Ok(())
+}
 
+// --------------------------------------------------
+// The lines of stdout from a successful run in sorted order
+fn sorted_lines(args: &[impl AsRef<OsStr>]) -> Result<Vec<String>> {
     let cmd = Command::cargo_bin(PRG)?.args(args).assert().success();
-    let out = cmd.get_output();
-    let stdout = String::from_utf8(out.stdout.clone())?;
-    let mut lines: Vec<&str> =
-        stdout.split('\n').filter(|s| !s.is_empty()).collect();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let mut lines: Vec<String> = stdout
+        .split('\n')
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+        .collect();
     lines.sort();
+    Ok(lines)
+}
 
-    assert_eq!(lines, expected);
+// --------------------------------------------------
+// An entry in a tree made for a test
+enum Node {
+    Dir(&'static str),
+    // A file of this many bytes readable by all
+    Bytes(&'static str, usize),
+    Text(&'static str, &'static str),
+    // A link and what it points to
+    Link(&'static str, &'static str),
+    Mode(&'static str, u32),
+    // Last modified this many days ago
+    Age(&'static str, u64),
+}
+
+// Files with known sizes, times and permissions
+const TREE: &[Node] = &[
+    Dir("empty"),
+    Dir("full"),
+    Bytes("empty.txt", 0),
+    Bytes("small.txt", 100),
+    Bytes("big.txt", 3000),
+    Mode("big.txt", 0o600),
+    Bytes("run.sh", 10),
+    Mode("run.sh", 0o755),
+    Bytes("full/file.txt", 1),
+    Bytes("old.txt", 10),
+    Age("old.txt", 10),
+];
+
+// A directory with a link to it, a link back to it from inside and a link
+// to nothing
+const LINKS: &[Node] = &[
+    Dir("d"),
+    Bytes("d/f", 1),
+    Link("d/loop", "../d"),
+    Link("dl", "d"),
+    Link("broken", "nowhere"),
+];
+
+// Ignore files that hide logs and build output but keep one log
+const IGNORES: &[Node] = &[
+    Dir("src"),
+    Dir("target/debug"),
+    Dir(".git"),
+    Dir("docs/build"),
+    Bytes("a.log", 0),
+    Bytes("keep.log", 0),
+    Bytes(".hidden", 0),
+    Bytes("src/main.rs", 0),
+    Bytes("src/main.log", 0),
+    Bytes("target/debug/app", 0),
+    Bytes(".git/HEAD", 0),
+    Bytes("docs/index.md", 0),
+    Bytes("docs/build/index.html", 0),
+    Text(".gitignore", "*.log\ntarget/\n"),
+    Text(".ignore", "!keep.log\n"),
+    Text("docs/.gitignore", "/build\n"),
+];
+
+// --------------------------------------------------
+fn make_tree(tree: &[Node]) -> Result<TempDir> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    for node in tree {
+        match *node {
+            Dir(path) => fs::create_dir_all(root.join(path))?,
+            Bytes(path, len) => {
+                fs::write(root.join(path), "x".repeat(len))?;
+                fs::set_permissions(
+                    root.join(path),
+                    fs::Permissions::from_mode(0o644),
+                )?;
+            }
+            Text(path, text) => fs::write(root.join(path), text)?,
+            Link(path, target) => symlink(target, root.join(path))?,
+            Mode(path, mode) => fs::set_permissions(
+                root.join(path),
+                fs::Permissions::from_mode(mode),
+            )?,
+            Age(path, days) => {
+                let age = Duration::from_secs(days * 24 * 60 * 60);
+                File::options()
+                    .write(true)
+                    .open(root.join(path))?
+                    .set_modified(SystemTime::now() - age)?;
+            }
+        }
+    }
+    Ok(dir)
+}
+
+// --------------------------------------------------
+// Runs below the root of a new tree with "{}" in the arguments replaced by
+// its path and compares the lines of stdout relative to it
+fn run_tree(tree: &[Node], args: &[&str], expected: &[&str]) -> Result<()> {
+    let dir = make_tree(tree)?;
+    let root = dir.path().to_string_lossy();
+    let mut all = vec![root.to_string(), "--mindepth=1".to_string()];
+    all.extend(args.iter().map(|arg| arg.replace("{}", &root)));
 
+    let prefix = format!("{root}/");
+    let mut lines: Vec<String> = sorted_lines(&all)?
+        .into_iter()
+        .map(|s| match s.strip_prefix(&prefix) {
+            Some(rest) => rest.to_string(),
+            None => s,
+        })
+        .collect();
+    lines.sort();
+
+    let mut expected = expected.to_vec();
+    expected.sort();
+    assert_eq!(lines, expected);
     Ok(())
 }
 
@@ -228,7 +390,7 @@ fn type_f_l() -> Result<()> {
 #[test]
 fn name_csv() -> Result<()> {
     run(
-        &["tests/inputs", "-n", ".*[.]csv"],
+        &["tests/inputs", "-n", "*.csv"],
         "tests/expected/name_csv.txt",
     )
 }
@@ -237,7 +399,7 @@ fn name_csv() -> Result<()> {
 #[test]
 fn name_csv_mp3() -> Result<()> {
     run(
-        &["tests/inputs", "-n", ".*[.]csv", "-n", ".*[.]mp3"],
+        &["tests/inputs", "-n", "*.csv", "-n", "*.mp3"],
         "tests/expected/name_csv_mp3.txt",
     )
 }
@@ -246,7 +408,7 @@ fn name_csv_mp3() -> Result<()> {
 #[test]
 fn name_txt_path_a_d() -> Result<()> {
     run(
-        &["tests/inputs/a", "tests/inputs/d", "--name", ".*.txt"],
+        &["tests/inputs/a", "tests/inputs/d", "--name", "*.txt"],
         "tests/expected/name_txt_path_a_d.txt",
     )
 }
@@ -254,14 +416,14 @@ fn name_txt_path_a_d() -> Result<()> {
 // --------------------------------------------------
 #[test]
 fn name_a() -> Result<()> {
-    run(&["tests/inputs", "-n", "a"], "tests/expected/name_a.txt")
+    run(&["tests/inputs", "-n", "a*"], "tests/expected/name_a.txt")
 }
 
 // --------------------------------------------------
 #[test]
 fn type_f_name_a() -> Result<()> {
     run(
-        &["tests/inputs", "-t", "f", "-n", "a"],
+        &["tests/inputs", "-t", "f", "-n", "a*"],
         "tests/expected/type_f_name_a.txt",
     )
 }
@@ -270,7 +432,7 @@ fn type_f_name_a() -> Result<()> {
 #[test]
 fn type_d_name_a() -> Result<()> {
     run(
-        &["tests/inputs", "--type", "d", "--name", "a"],
+        &["tests/inputs", "--type", "d", "--name", "a*"],
         "tests/expected/type_d_name_a.txt",
     )
 }
@@ -283,32 +445,833 @@ fn path_g() -> Result<()> {
 
 // --------------------------------------------------
 #[test]
-#[cfg(not(windows))]
 fn unreadable_dir() -> Result<()> {
-    let dirname = "tests/inputs/cant-touch-this";
-    if !Path::new(dirname).exists() {
-        fs::create_dir(dirname)?;
+    // Permissions don't keep root out
+    if users::get_effective_uid() == 0 {
+        eprintln!("skipping unreadable_dir: root can read any directory");
+        return Ok(());
     }
 
-    std::process::Command::new("chmod")
-        .args(["000", dirname])
-        .status()
-        .expect("failed");
+    let dir = make_tree(&[
+        Dir("cant-touch-this"),
+        Bytes("f", 1),
+        Mode("cant-touch-this", 0o000),
+    ])?;
+    let root = dir.path().to_string_lossy();
+    let output = Command::cargo_bin(PRG)?.arg(&*root).output()?;
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8(output.stdout)?.replace(&*root, "");
+    let mut lines: Vec<&str> = stdout.lines().collect();
+    lines.sort();
+    assert_eq!(lines, ["", "/cant-touch-this", "/f"]);
+
+    let stderr = String::from_utf8(output.stderr)?.replace(&*root, "");
+    assert_eq!(stderr, "findr: /cant-touch-this: Permission denied\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn missing_root() -> Result<()> {
+    // Fails the run for anyone, unlike an unreadable directory
+    let dir = make_tree(TREE)?;
+    let root = dir.path().to_string_lossy();
+    let output = Command::cargo_bin(PRG)?
+        .args([format!("{root}/missing"), format!("{root}/full")])
+        .output()?;
+    assert_eq!(output.status.code(), Some(1));
+
+    let stdout = String::from_utf8(output.stdout)?.replace(&*root, "");
+    let mut lines: Vec<&str> = stdout.lines().collect();
+    lines.sort();
+    assert_eq!(lines, ["/full", "/full/file.txt"]);
+
+    let stderr = String::from_utf8(output.stderr)?.replace(&*root, "");
+    assert_eq!(stderr, "findr: /missing: No such file or directory\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn maxdepth_1() -> Result<()> {
+    run(
+        &["tests/inputs", "--maxdepth", "1"],
+        "tests/expected/maxdepth_1.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mindepth_2_maxdepth_2() -> Result<()> {
+    run(
+        &["tests/inputs", "--mindepth", "2", "--maxdepth", "2"],
+        "tests/expected/mindepth_2_maxdepth_2.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mindepth_3() -> Result<()> {
+    run(
+        &["tests/inputs", "--mindepth", "3"],
+        "tests/expected/mindepth_3.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn find_style_depths() -> Result<()> {
+    run(
+        &["tests/inputs", "-maxdepth", "1"],
+        "tests/expected/maxdepth_1.txt",
+    )?;
+    run(
+        &["tests/inputs", "-mindepth", "2", "-maxdepth", "2"],
+        "tests/expected/mindepth_2_maxdepth_2.txt",
+    )?;
+    run(
+        &["tests/inputs", "-mindepth", "3"],
+        "tests/expected/mindepth_3.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn maxdepth_2_name_csv() -> Result<()> {
+    run(
+        &["tests/inputs", "-maxdepth", "2", "-name", "*.csv"],
+        "tests/expected/maxdepth_2_name_csv.txt",
+    )?;
+
+    // Wherever it is in the expression
+    run(
+        &[
+            "tests/inputs",
+            "-name",
+            "*.csv",
+            "-o",
+            "-maxdepth",
+            "2",
+            "-false",
+        ],
+        "tests/expected/maxdepth_2_name_csv.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn type_f_size_2c() -> Result<()> {
+    run(
+        &["tests/inputs", "-t", "f", "--size", "2c"],
+        "tests/expected/type_f_size_2c.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn size_more() -> Result<()> {
+    run_tree(TREE, &["-t", "f", "--size", "+1k"], &["big.txt"])
+}
+
+// --------------------------------------------------
+#[test]
+fn size_less_rounds_up() -> Result<()> {
+    run_tree(TREE, &["-t", "f", "--size", "-1k"], &["empty.txt"])
+}
 
-    let cmd = Command::cargo_bin(PRG)?
-        .arg("tests/inputs")
+// --------------------------------------------------
+#[test]
+fn size_exactly() -> Result<()> {
+    run_tree(TREE, &["--size", "100c"], &["small.txt"])
+}
+
+// --------------------------------------------------
+#[test]
+fn size_range() -> Result<()> {
+    run_tree(
+        TREE,
+        &["-t", "f", "--size", "+0", "--size", "-2"],
+        &["full/file.txt", "old.txt", "run.sh", "small.txt"],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mtime_more() -> Result<()> {
+    run_tree(TREE, &["--mtime", "+7"], &["old.txt"])
+}
+
+// --------------------------------------------------
+#[test]
+fn mtime_less() -> Result<()> {
+    run_tree(
+        TREE,
+        &["-t", "f", "--mtime", "-1"],
+        &[
+            "big.txt",
+            "empty.txt",
+            "full/file.txt",
+            "run.sh",
+            "small.txt",
+        ],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn newer() -> Result<()> {
+    run_tree(
+        TREE,
+        &["-t", "f", "--newer", "{}/old.txt"],
+        &[
+            "big.txt",
+            "empty.txt",
+            "full/file.txt",
+            "run.sh",
+            "small.txt",
+        ],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn perm_exactly() -> Result<()> {
+    run_tree(TREE, &["-t", "f", "--perm", "600"], &["big.txt"])
+}
+
+// --------------------------------------------------
+#[test]
+fn perm_all() -> Result<()> {
+    run_tree(TREE, &["-t", "f", "--perm", "-755"], &["run.sh"])
+}
+
+// --------------------------------------------------
+#[test]
+fn perm_any() -> Result<()> {
+    run_tree(
+        TREE,
+        &["-t", "f", "--perm", "/044"],
+        &[
+            "empty.txt",
+            "full/file.txt",
+            "old.txt",
+            "run.sh",
+            "small.txt",
+        ],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn user_group() -> Result<()> {
+    let meta = fs::metadata("tests/inputs")?;
+    let uid = meta.uid().to_string();
+    let gid = meta.gid().to_string();
+    run(
+        &["tests/inputs", "--user", &uid, "--group", &gid],
+        "tests/expected/path1.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn empty() -> Result<()> {
+    run_tree(TREE, &["--empty"], &["empty", "empty.txt"])
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_size() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--size", "10x"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown unit 'x'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_perm() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--perm", "999"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("invalid value '999'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_user() -> Result<()> {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args(["--user", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("no such user"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_newer() -> Result<()> {
+    let bad = gen_bad_file();
+    let expected = format!("{bad}: .* [(]os error 2[)]");
+    Command::cargo_bin(PRG)?
+        .args(["--newer", &bad])
+        .assert()
+        .failure()
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_csv_mp3_not_l() -> Result<()> {
+    run(
+        &[
+            "tests/inputs",
+            "(",
+            "-name",
+            "*.csv",
+            "-or",
+            "-name",
+            "*.mp3",
+            ")",
+            "-and",
+            "-not",
+            "-type",
+            "l",
+        ],
+        "tests/expected/expr_csv_mp3_not_l.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_d_a_or_l() -> Result<()> {
+    run(
+        &[
+            "tests/inputs",
+            "-type",
+            "d",
+            "-name",
+            "a",
+            "-o",
+            "-type",
+            "l",
+        ],
+        "tests/expected/expr_d_a_or_l.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_not_d_txt() -> Result<()> {
+    run(
+        &[
+            "tests/inputs/a",
+            "tests/inputs/d",
+            "!",
+            "(",
+            "-type",
+            "d",
+            "-o",
+            "-name",
+            "*.txt",
+            ")",
+            "-size",
+            "-2k",
+        ],
+        "tests/expected/expr_not_d_txt.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn expr_after_options() -> Result<()> {
+    run(
+        &["tests/inputs", "-t", "d", "-name", "a", "-o", "-type", "l"],
+        "tests/expected/type_d_name_a.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unbalanced_parens() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "(", "-type", "f"])
+        .assert()
+        .failure()
+        .stderr(
+            "findr: invalid expression; expected ')' but did not find one\n",
+        );
+
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-type", "f", ")"])
+        .assert()
+        .failure()
+        .stderr("findr: invalid expression; too many ')'\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_unknown_predicate() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-bogus"])
+        .assert()
+        .failure()
+        .stderr("findr: unknown predicate '-bogus'\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn type_f_print0() -> Result<()> {
+    let contents = fs::read("tests/expected/type_f_print0.txt")?;
+    let mut expected: Vec<&[u8]> = contents.split(|&b| b == 0).collect();
+    expected.sort();
+
+    let output = Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-type", "f", "-print0"])
+        .output()?;
+    assert!(output.status.success());
+    let mut records: Vec<&[u8]> = output.stdout.split(|&b| b == 0).collect();
+    records.sort();
+
+    assert_eq!(records, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn type_f_printf() -> Result<()> {
+    run(
+        &[
+            "tests/inputs",
+            "-type",
+            "f",
+            "-printf",
+            "%p|%f|%h|%P|%H|%d|%y|%s\\n",
+        ],
+        "tests/expected/type_f_printf.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_csv_print_d() -> Result<()> {
+    run(
+        &[
+            "tests/inputs",
+            "-name",
+            "*.csv",
+            "-printf",
+            "[%-8f][%8y]\\n",
+            "-o",
+            "-type",
+            "d",
+            "-print",
+        ],
+        "tests/expected/printf_csv_print_d.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_mode_time() -> Result<()> {
+    run_tree(
+        TREE,
+        &["-name", "run*", "-printf", "%P %m %M %TY\\n"],
+        &[&format!(
+            "run.sh 755 -rwxr-xr-x {}",
+            chrono::Local::now().format("%Y")
+        )],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn delete() -> Result<()> {
+    let dir = make_tree(TREE)?;
+    Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args(["-name", "*.txt", "-delete"])
+        .assert()
+        .success()
+        .stdout("");
+
+    let mut left: Vec<_> = fs::read_dir(dir.path())?
+        .map(|entry| entry.map(|e| e.file_name()))
+        .collect::<Result<_, _>>()?;
+    left.sort();
+    assert_eq!(left, ["empty", "full", "run.sh"]);
+    assert!(fs::read_dir(dir.path().join("full"))?.next().is_none());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn delete_tree() -> Result<()> {
+    let dir = make_tree(TREE)?;
+    let root = dir.path().join("full");
+    fs::create_dir_all(root.join("a/b/c"))?;
+    fs::write(root.join("a/b/c/d.txt"), "d")?;
+    Command::cargo_bin(PRG)?
+        .arg(&root)
+        .arg("-delete")
         .assert()
         .success();
-    fs::remove_dir(dirname)?;
+    assert!(!root.exists());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_echo() -> Result<()> {
+    run(
+        &[
+            "tests/inputs",
+            "-type",
+            "f",
+            "-exec",
+            "echo",
+            "found",
+            "{}",
+            ";",
+        ],
+        "tests/expected/exec_echo.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn execdir_echo() -> Result<()> {
+    run(
+        &["tests/inputs", "-type", "f", "-execdir", "echo", "{}", ";"],
+        "tests/expected/execdir_echo.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_batch() -> Result<()> {
+    let output = Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-type", "f", "-exec", "echo", "{}", "+"])
+        .output()?;
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut files: Vec<&str> = stdout.split_whitespace().collect();
+    files.sort();
+    let contents = fs::read_to_string("tests/expected/type_f.txt")?;
+    let mut expected: Vec<&str> = contents.lines().collect();
+    expected.sort();
+
+    assert_eq!(stdout.lines().count(), 1);
+    assert_eq!(files, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_as_test() -> Result<()> {
+    run(
+        &["tests/inputs", "-exec", "test", "-d", "{}", ";", "-print"],
+        "tests/expected/type_d.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn exec_status() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-exec", "false", "{}", ";"])
+        .assert()
+        .success()
+        .stdout("");
+
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-exec", "false", "{}", "+"])
+        .assert()
+        .failure()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_exec_no_terminator() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-exec", "echo", "{}"])
+        .assert()
+        .failure()
+        .stderr("findr: missing argument to '-exec'\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn iname_csv() -> Result<()> {
+    run(
+        &["tests/inputs", "--iname", "*.CSV"],
+        "tests/expected/name_csv.txt",
+    )?;
+    run(
+        &["tests/inputs", "-iname", "*.CSV"],
+        "tests/expected/name_csv.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn name_is_whole_name() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-n", "csv"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn path_glob_a() -> Result<()> {
+    run(
+        &["tests/inputs", "-path", "*/a/*"],
+        "tests/expected/path_glob_a.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn ipath_b_csv() -> Result<()> {
+    run(
+        &["tests/inputs", "-ipath", "*/B/*.csv"],
+        "tests/expected/ipath_b_csv.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_abc() -> Result<()> {
+    run(
+        &["tests/inputs", "-regex", ".*/[a-c][.][a-z0-9]+"],
+        "tests/expected/regex_abc.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn regex_is_anchored() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-regex", "[a-z]+[.]csv"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn prune_a() -> Result<()> {
+    run(
+        &["tests/inputs", "-name", "a", "-prune", "-o", "-print"],
+        "tests/expected/prune_a.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn prune_prints_by_default() -> Result<()> {
+    run(
+        &["tests/inputs", "-type", "d", "-name", "[ad]", "-prune"],
+        "tests/expected/prune_d_print.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_regex() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-regex", "("])
+        .assert()
+        .failure()
+        .stderr(predicate::str::starts_with(
+            "findr: invalid argument '(' to '-regex'",
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+// Runs with the given arguments before a path in the links and compares
+// the lines of stdout relative to it
+fn run_links(path: &str, args: &[&str], expected: &[&str]) -> Result<()> {
+    let dir = make_tree(LINKS)?;
+    let path = format!("{}/{path}", dir.path().display());
+    let lines =
+        sorted_lines(&[args, &[&path, "-printf", "%P %y\\n"]].concat())?;
+    assert_eq!(lines, expected);
+    Ok(())
+}
 
-    let out = cmd.get_output();
-    let stdout = String::from_utf8(out.stdout.clone())?;
-    let lines: Vec<&str> =
-        stdout.split('\n').filter(|s| !s.is_empty()).collect();
+// --------------------------------------------------
+#[test]
+fn follow_none() -> Result<()> {
+    let all = [" d", "broken l", "d d", "d/f f", "d/loop l", "dl l"];
+    for args in [&[][..], &["-P"], &["-L", "-P"]] {
+        run_links("", args, &all)?;
+    }
+    run_links("dl", &["-P"], &[" l"])
+}
 
-    assert_eq!(lines.len(), 17);
+// --------------------------------------------------
+#[test]
+fn follow_roots() -> Result<()> {
+    run_links("dl", &["-H"], &[" d", "f f", "loop l"])?;
+    run_links(
+        "",
+        &["-H"],
+        &[" d", "broken l", "d d", "d/f f", "d/loop l", "dl l"],
+    )?;
+
+    run(
+        &["-H", "tests/inputs/d/b.csv", "-printf", "%p %y\\n"],
+        "tests/expected/follow_roots_b_csv.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn follow_all() -> Result<()> {
+    // Loops are reported and make the run fail
+    let dir = make_tree(LINKS)?;
+    let root = dir.path().to_string_lossy();
+    let output = Command::cargo_bin(PRG)?
+        .args(["-L", &format!("{root}/"), "-printf", "%P %y\\n"])
+        .output()?;
+    assert!(!output.status.success());
+
+    let stdout = String::from_utf8(output.stdout)?;
+    let mut lines: Vec<&str> = stdout.lines().collect();
+    lines.sort();
+    assert_eq!(lines, [" d", "broken l", "d d", "d/f f", "dl d", "dl/f f"]);
+
+    let stderr = String::from_utf8(output.stderr)?.replace(&*root, "");
+    let mut errors: Vec<&str> = stderr.lines().collect();
+    errors.sort();
+    assert_eq!(
+        errors,
+        [
+            "findr: File system loop detected; '/d/loop' is part of the \
+            same file system loop as '/d'.",
+            "findr: File system loop detected; '/dl/loop' is part of the \
+            same file system loop as '/dl'.",
+        ]
+    );
+
+    run_links("broken", &["-L"], &[" l"])?;
+
+    run(
+        &["-L", "tests/inputs", "-type", "f"],
+        "tests/expected/follow_type_f.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn xdev() -> Result<()> {
+    // Only meaningful where /proc is mounted apart from /
+    let proc = fs::metadata("/proc").map(|meta| meta.dev());
+    if proc.is_err() || proc? == fs::metadata("/")?.dev() {
+        return Ok(());
+    }
+
+    let args = ["/", "--maxdepth", "2", "-path", "/proc/*"];
+    Command::cargo_bin(PRG)?
+        .arg("--xdev")
+        .args(args)
+        .assert()
+        .success()
+        .stdout("");
+    Command::cargo_bin(PRG)?
+        .args(["/", "-xdev", "-maxdepth", "2", "-path", "/proc/*"])
+        .assert()
+        .success()
+        .stdout("");
+
+    let output = Command::cargo_bin(PRG)?.args(args).output()?;
+    assert!(!output.stdout.is_empty());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn broken_pipe() -> Result<()> {
+    // The reader is gone by the time the entry is printed
+    let mut child = process::Command::new(cargo_bin(PRG))
+        .args(["tests/inputs", "--maxdepth", "0"])
+        .args(["-exec", "sleep", "0.2", ";", "-print"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    drop(child.stdout.take());
+    let output = child.wait_with_output()?;
+    assert_eq!(String::from_utf8(output.stderr)?, "");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sort() -> Result<()> {
+    let expected = fs::read_to_string("tests/expected/sort.txt")?;
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "--sort"])
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn respect_ignore() -> Result<()> {
+    let expected =
+        ["docs", "docs/index.md", "keep.log", "src", "src/main.rs"];
+    for args in [&[][..], &["--sort"]] {
+        let ignore = [args, &["--respect-ignore", "-print"]].concat();
+        run_tree(IGNORES, &ignore, &expected)?;
+    }
+
+    // Without ignore files only hidden entries are skipped
+    run_tree(
+        TREE,
+        &["--respect-ignore", "-name", "*.txt"],
+        &[
+            "big.txt",
+            "empty.txt",
+            "full/file.txt",
+            "old.txt",
+            "small.txt",
+        ],
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn respect_ignore_delete() -> Result<()> {
+    let dir = make_tree(IGNORES)?;
+    Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args(["--respect-ignore", "-name", "*.log", "-delete"])
+        .assert()
+        .success();
 
-    let stderr = String::from_utf8(out.stderr.clone())?;
-    assert!(stderr.contains("cant-touch-this: Permission denied"));
+    assert!(!dir.path().join("keep.log").exists());
+    assert!(dir.path().join("a.log").exists());
+    assert!(dir.path().join("src/main.log").exists());
     Ok(())
 }
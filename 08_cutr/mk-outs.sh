@@ -5,6 +5,11 @@ set -u
 CSV="tests/inputs/movies1.csv"
 TSV="tests/inputs/movies1.tsv"
 BOOKS="tests/inputs/books.tsv"
+MIXED="tests/inputs/mixed.tsv"
+MOVIES2="tests/inputs/movies2.csv"
+QUOTES="tests/inputs/quotes.csv"
+CRLF="tests/inputs/crlf.csv"
+LATIN1="tests/inputs/latin1.csv"
 OUT_DIR="tests/expected"
 
 [[ ! -d "$OUT_DIR" ]] && mkdir -p "$OUT_DIR"
@@ -26,3 +31,55 @@ for POS in 1 2 8 1-2 2-3 1-8; do
 done
 
 echo -e "AA\nÉÉ\nSS\nJJ" > "$OUT_DIR/books.c1,1.out"
+
+for FLD in 2- -2 2-2; do
+    cut -f $FLD      $TSV > "$OUT_DIR/$(basename $TSV).f${FLD}.out"
+    cut -f $FLD -d , $CSV > "$OUT_DIR/$(basename $CSV).f${FLD}.dcomma.out"
+done
+
+for POS in 3- -3; do
+    cut -b $POS $TSV > "$OUT_DIR/$(basename $TSV).b${POS}.out"
+    cut -c $POS $TSV > "$OUT_DIR/$(basename $TSV).c${POS}.out"
+done
+
+cut --complement -f 2 $TSV > "$OUT_DIR/$(basename $TSV).f2.complement.out"
+cut --complement -f 1 -d , $CSV \
+    > "$OUT_DIR/$(basename $CSV).f1.dcomma.complement.out"
+cut --complement -c 2-3,5- $TSV \
+    > "$OUT_DIR/$(basename $TSV).c2-3,5-.complement.out"
+
+for FLD in 2 1,3; do
+    cut -f $FLD    $MIXED > "$OUT_DIR/$(basename $MIXED).f${FLD}.out"
+    cut -f $FLD -s $MIXED > "$OUT_DIR/$(basename $MIXED).f${FLD}.s.out"
+done
+
+cut -f 1,3 --output-delimiter " :: " $MIXED \
+    > "$OUT_DIR/$(basename $MIXED).f1,3.odcolons.out"
+cut -f 2- -d , --output-delimiter $'\t' $CSV \
+    > "$OUT_DIR/$(basename $CSV).f2-.dcomma.odtab.out"
+
+# Raw mode splits like cut, while CSV mode reads and writes quoted fields
+for FLD in 1 2-; do
+    cut -d , -f $FLD $MOVIES2 \
+        > "$OUT_DIR/$(basename $MOVIES2).f${FLD}.dcomma.raw.out"
+done
+
+# Carriage returns and bytes that aren't UTF-8 are kept as they are
+for INPUT in $CRLF $LATIN1; do
+    cut -d , -f 2 $INPUT > "$OUT_DIR/$(basename $INPUT).f2.dcomma.raw.out"
+done
+
+echo -e 'title\nThe Blues Brothers\nLes Misérables\n"To Sir, with Love"' \
+    > "$OUT_DIR/$(basename $MOVIES2).f1.dcomma.out"
+echo -e 'title\nThe Blues Brothers\nLes Misérables\nTo Sir, with Love' \
+    > "$OUT_DIR/$(basename $MOVIES2).f1.dcomma.norequote.out"
+cat > "$OUT_DIR/$(basename $QUOTES).f2,1.out" << 'END'
+said,name
+'If I\'d asked people, they\'d have said "faster horses"','Ford, Henry'
+'It ain\'t over till it\'s over',Yogi Berra
+END
+cat > "$OUT_DIR/$(basename $QUOTES).f2,1.norequote.out" << 'END'
+said | name
+If I'd asked people, they'd have said "faster horses" | Ford, Henry
+It ain't over till it's over | Yogi Berra
+END
@@ -1,12 +1,14 @@
 use anyhow::{anyhow, bail, Result};
-use clap::Parser;
-use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use clap::{Parser, ValueEnum};
+use cliutil::{open, Reporter};
+use csv::ReaderBuilder;
 use regex::Regex;
 use std::{
-    fs::File,
-    io::{self, BufRead, BufReader},
+    ffi::{OsStr, OsString},
+    io::{self, BufRead, Write},
     num::NonZeroUsize,
     ops::Range,
+    os::unix::ffi::OsStrExt,
 };
 
 #[derive(Debug, Parser)]
@@ -19,7 +21,45 @@ struct Args {
 
     /// Field delimiter
     #[arg(short, long, value_name = "DELIMITER", default_value = "\t")]
-    delimiter: String,
+    delimiter: OsString,
+
+    /// Delimiter between selected fields [default: DELIMITER]
+    #[arg(
+        long,
+        value_name = "STRING",
+        conflicts_with_all = ["bytes", "chars"]
+    )]
+    output_delimiter: Option<OsString>,
+
+    /// Skip lines without the field delimiter
+    #[arg(short = 's', long, conflicts_with_all = ["bytes", "chars"])]
+    only_delimited: bool,
+
+    /// Select everything except the given positions
+    #[arg(long)]
+    complement: bool,
+
+    /// How lines are split into fields; raw splits on every delimiter
+    #[arg(
+        long,
+        value_name = "MODE",
+        value_enum,
+        default_value_t = Mode::Csv,
+        conflicts_with_all = ["bytes", "chars"]
+    )]
+    mode: Mode,
+
+    /// Quote character for --mode csv [default: "]
+    #[arg(long, value_name = "CHAR", conflicts_with_all = ["bytes", "chars"])]
+    quote: Option<OsString>,
+
+    /// Character escaping quotes for --mode csv, besides doubling them
+    #[arg(long, value_name = "CHAR", conflicts_with_all = ["bytes", "chars"])]
+    escape: Option<OsString>,
+
+    /// Write fields for --mode csv as read, without quoting them again
+    #[arg(long, conflicts_with_all = ["bytes", "chars"])]
+    no_requote: bool,
 
     #[command(flatten)]
     extract: ArgsExtract,
@@ -29,19 +69,35 @@ struct Args {
 #[group(required = true, multiple = false)]
 struct ArgsExtract {
     /// Selected fields
-    #[arg(short, long, value_name = "FIELDS")]
+    #[arg(short, long, value_name = "FIELDS", allow_hyphen_values = true)]
     fields: Option<String>,
 
     /// Selected bytes
-    #[arg(short, long, value_name = "BYTES")]
+    #[arg(short, long, value_name = "BYTES", allow_hyphen_values = true)]
     bytes: Option<String>,
 
     /// Selected chars
-    #[arg(short, long, value_name = "CHARS")]
+    #[arg(short, long, value_name = "CHARS", allow_hyphen_values = true)]
     chars: Option<String>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Mode {
+    Raw,
+    Csv,
+}
+
+// How fields are quoted when written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Quoting {
+    quote: u8,
+    escape: Option<u8>,
+}
+
+// Positions are ranges of zero-based indexes where an open-ended range
+// like "3-" runs until the end of the line
 type PositionList = Vec<Range<usize>>;
+const END: usize = usize::MAX;
 
 #[derive(Debug)]
 enum Extract {
@@ -52,31 +108,57 @@ enum Extract {
 
 // --------------------------------------------------
 fn main() {
-    if let Err(e) = run(Args::parse()) {
-        eprintln!("{e}");
-        std::process::exit(1);
+    let mut reporter = Reporter::new(env!("CARGO_BIN_NAME"));
+    if let Err(e) = run(Args::parse(), &mut reporter) {
+        reporter.report(e);
     }
+    std::process::exit(reporter.exit_code());
 }
 
 // --------------------------------------------------
-fn run(args: Args) -> Result<()> {
-    let delim_bytes = args.delimiter.as_bytes();
-    if delim_bytes.len() != 1 {
-        bail!(r#"--delim "{}" must be a single byte"#, args.delimiter);
+fn run(args: Args, reporter: &mut Reporter) -> Result<()> {
+    let delimiter = parse_byte("delim", &args.delimiter)?;
+    let output_delimiter =
+        args.output_delimiter.as_deref().unwrap_or(&args.delimiter);
+
+    if args.mode == Mode::Raw
+        && (args.quote.is_some() || args.escape.is_some() || args.no_requote)
+    {
+        bail!("--quote, --escape and --no-requote need --mode csv");
     }
-    let delimiter: u8 = *delim_bytes.first().unwrap();
+    let quoting = Quoting {
+        quote: args
+            .quote
+            .as_deref()
+            .map(|quote| parse_byte("quote", quote))
+            .transpose()?
+            .unwrap_or(b'"'),
+        escape: args
+            .escape
+            .as_deref()
+            .map(|escape| parse_byte("escape", escape))
+            .transpose()?,
+    };
+    let requote =
+        (args.mode == Mode::Csv && !args.no_requote).then_some(quoting);
+
+    let parse = |list: String| {
+        parse_pos(list).map(|pos| {
+            if args.complement {
+                complement(pos)
+            } else {
+                pos
+            }
+        })
+    };
 
     let extract = if let Some(fields) =
-        args.extract.fields.map(parse_pos).transpose()?
+        args.extract.fields.map(parse).transpose()?
     {
         Extract::Fields(fields)
-    } else if let Some(bytes) =
-        args.extract.bytes.map(parse_pos).transpose()?
-    {
+    } else if let Some(bytes) = args.extract.bytes.map(parse).transpose()? {
         Extract::Bytes(bytes)
-    } else if let Some(chars) =
-        args.extract.chars.map(parse_pos).transpose()?
-    {
+    } else if let Some(chars) = args.extract.chars.map(parse).transpose()? {
         Extract::Chars(chars)
     } else {
         unreachable!("Must have --fields, --bytes, or --chars");
@@ -84,22 +166,51 @@ fn run(args: Args) -> Result<()> {
 
     for filename in &args.files {
         match open(filename) {
-            Err(err) => eprintln!("{filename}: {err}"),
-            Ok(file) => match &extract {
+            Err(err) => reporter.report_file(filename, err),
+            Ok(mut file) => match &extract {
                 Extract::Fields(field_pos) => {
-                    let mut reader = ReaderBuilder::new()
-                        .delimiter(delimiter)
-                        .has_headers(false)
-                        .from_reader(file);
-
-                    let mut wtr = WriterBuilder::new()
-                        .delimiter(delimiter)
-                        .from_writer(io::stdout());
-
-                    for record in reader.records() {
-                        wtr.write_record(extract_fields(
-                            &record?, field_pos,
-                        ))?;
+                    let mut out = io::stdout().lock();
+                    let mut cut = |fields: &[&[u8]]| {
+                        select_fields(fields, field_pos, args.only_delimited)
+                            .map_or(Ok(()), |fields| {
+                                write_fields(
+                                    &mut out,
+                                    &fields,
+                                    output_delimiter.as_bytes(),
+                                    requote,
+                                )
+                            })
+                    };
+
+                    match args.mode {
+                        // Bytes are split and written as they are, like cut,
+                        // so only the newline is taken off each line
+                        Mode::Raw => {
+                            let mut line = vec![];
+                            while file.read_until(b'\n', &mut line)? > 0 {
+                                let fields: Vec<_> = line
+                                    .strip_suffix(b"\n")
+                                    .unwrap_or(&line)
+                                    .split(|&byte| byte == delimiter)
+                                    .collect();
+                                cut(&fields)?;
+                                line.clear();
+                            }
+                        }
+                        Mode::Csv => {
+                            let mut reader = ReaderBuilder::new()
+                                .delimiter(delimiter)
+                                .quote(quoting.quote)
+                                .escape(quoting.escape)
+                                .has_headers(false)
+                                .flexible(true)
+                                .from_reader(file);
+                            for record in reader.byte_records() {
+                                let record = record?;
+                                let fields: Vec<_> = record.iter().collect();
+                                cut(&fields)?;
+                            }
+                        }
                     }
                 }
                 Extract::Bytes(byte_pos) => {
@@ -120,10 +231,16 @@ fn run(args: Args) -> Result<()> {
 }
 
 // --------------------------------------------------
-fn open(filename: &str) -> Result<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
+// Delimiters and quotes must be a single byte like "," though it needn't
+// be UTF-8
+fn parse_byte(name: &str, value: impl AsRef<OsStr>) -> Result<u8> {
+    let value = value.as_ref();
+    match value.as_bytes() {
+        [byte] => Ok(*byte),
+        _ => bail!(
+            r#"--{name} "{}" must be a single byte"#,
+            value.to_string_lossy()
+        ),
     }
 }
 
@@ -135,62 +252,151 @@ fn open(filename: &str) -> Result<Box<dyn BufRead>> {
 // one less than the number represented by the original input.
 fn parse_index(input: &str) -> Result<usize> {
     let value_error = || anyhow!(r#"illegal list value: "{input}""#);
-    input
-        .starts_with('+')
-        .then(|| Err(value_error()))
-        .unwrap_or_else(|| {
-            input
-                .parse::<NonZeroUsize>()
-                .map(|n| usize::from(n) - 1)
-                .map_err(|_| value_error())
-        })
+    if input.starts_with('+') {
+        Err(value_error())
+    } else {
+        input
+            .parse::<NonZeroUsize>()
+            .map(|n| usize::from(n) - 1)
+            .map_err(|_| value_error())
+    }
 }
 
 // --------------------------------------------------
+// Parse a list like "1,3-5,7-" where "-N" starts at the first position
+// and "N-" runs until the end of the line
 fn parse_pos(range: String) -> Result<PositionList> {
-    let range_re = Regex::new(r"^(\d+)-(\d+)$").unwrap();
+    let range_re = Regex::new(r"^(\d*)-(\d*)$").unwrap();
     range
         .split(',')
         .map(|val| {
             parse_index(val).map(|n| n..n + 1).or_else(|e| {
-                range_re.captures(val).ok_or(e).and_then(|captures| {
-                    let n1 = parse_index(&captures[1])?;
-                    let n2 = parse_index(&captures[2])?;
-                    if n1 >= n2 {
-                        bail!(
-                            "First number in range ({}) \
-                            must be lower than second number ({})",
-                            n1 + 1,
-                            n2 + 1
-                        );
-                    }
-                    Ok(n1..n2 + 1)
-                })
+                let captures = range_re
+                    .captures(val)
+                    .filter(|caps| !caps[1].is_empty() || !caps[2].is_empty())
+                    .ok_or(e)?;
+                let n1 = match &captures[1] {
+                    "" => 0,
+                    n1 => parse_index(n1)?,
+                };
+                let n2 = match &captures[2] {
+                    "" => return Ok(n1..END),
+                    n2 => parse_index(n2)?,
+                };
+                if n1 > n2 {
+                    bail!(
+                        "First number in range ({}) \
+                        must not be greater than second number ({})",
+                        n1 + 1,
+                        n2 + 1
+                    );
+                }
+                Ok(n1..n2 + 1)
             })
         })
-        .collect::<Result<_, _>>()
-        .map_err(From::from)
+        .collect()
 }
 
 // --------------------------------------------------
-fn extract_fields<'a>(
-    record: &'a StringRecord,
+// The positions not in the list in ascending order
+fn complement(mut pos: PositionList) -> PositionList {
+    pos.sort_by_key(|range| range.start);
+    let mut ranges = vec![];
+    let mut start = 0;
+    for range in pos {
+        if range.start > start {
+            ranges.push(start..range.start);
+        }
+        start = start.max(range.end);
+    }
+    if start < END {
+        ranges.push(start..END);
+    }
+    ranges
+}
+
+// --------------------------------------------------
+// Like cut, a line without the delimiter is kept whole unless it's to be
+// skipped
+fn select_fields<T: Copy>(
+    fields: &[T],
+    field_pos: &[Range<usize>],
+    only_delimited: bool,
+) -> Option<Vec<T>> {
+    if fields.len() > 1 {
+        Some(extract_fields(fields, field_pos))
+    } else if only_delimited {
+        None
+    } else {
+        Some(fields.to_vec())
+    }
+}
+
+// --------------------------------------------------
+fn extract_fields<T: Copy>(
+    record: &[T],
     field_pos: &[Range<usize>],
-) -> Vec<&'a str> {
+) -> Vec<T> {
     field_pos
         .iter()
         .cloned()
-        .flat_map(|range| range.filter_map(|i| record.get(i)))
+        .flat_map(|range| {
+            (range.start..range.end.min(record.len()))
+                .filter_map(|i| record.get(i).copied())
+        })
         .collect()
 }
 
+// --------------------------------------------------
+// Write fields on one line, quoting those that would otherwise be split
+// apart when read back unless they're written as they are
+fn write_fields(
+    out: &mut impl Write,
+    fields: &[&[u8]],
+    delimiter: &[u8],
+    quoting: Option<Quoting>,
+) -> io::Result<()> {
+    for (i, field) in fields.iter().enumerate() {
+        if i > 0 {
+            out.write_all(delimiter)?;
+        }
+        match quoting {
+            Some(Quoting { quote, escape })
+                if delimiter.is_empty()
+                    || field
+                        .windows(delimiter.len())
+                        .any(|w| w == delimiter)
+                    || field
+                        .iter()
+                        .any(|b| [quote, b'\r', b'\n'].contains(b)) =>
+            {
+                let escape = escape.unwrap_or(quote);
+                out.write_all(&[quote])?;
+                for (i, part) in field.split(|&b| b == quote).enumerate() {
+                    if i > 0 {
+                        out.write_all(&[escape, quote])?;
+                    }
+                    out.write_all(part)?;
+                }
+                out.write_all(&[quote])?;
+            }
+            _ => out.write_all(field)?,
+        }
+    }
+    writeln!(out)
+}
+
 // --------------------------------------------------
 fn extract_bytes(line: &str, byte_pos: &[Range<usize>]) -> String {
     let bytes = line.as_bytes();
     let selected: Vec<_> = byte_pos
         .iter()
         .cloned()
-        .flat_map(|range| range.filter_map(|i| bytes.get(i)).copied())
+        .flat_map(|range| {
+            (range.start..range.end.min(bytes.len()))
+                .filter_map(|i| bytes.get(i))
+                .copied()
+        })
         .collect();
     String::from_utf8_lossy(&selected).into_owned()
 }
@@ -201,15 +407,21 @@ fn extract_chars(line: &str, char_pos: &[Range<usize>]) -> String {
     char_pos
         .iter()
         .cloned()
-        .flat_map(|range| range.filter_map(|i| chars.get(i)))
+        .flat_map(|range| {
+            (range.start..range.end.min(chars.len()))
+                .filter_map(|i| chars.get(i))
+        })
         .collect()
 }
 
 // --------------------------------------------------
 #[cfg(test)]
+#[allow(clippy::single_range_in_vec_init)]
 mod unit_tests {
-    use super::{extract_bytes, extract_chars, extract_fields, parse_pos};
-    use csv::StringRecord;
+    use super::{
+        complement, extract_bytes, extract_chars, extract_fields, parse_byte,
+        parse_pos, select_fields, write_fields, Quoting, END,
+    };
     use pretty_assertions::assert_eq;
 
     #[test]
@@ -293,28 +505,22 @@ mod unit_tests {
         let res = parse_pos("1,".to_string());
         assert!(res.is_err());
 
-        let res = parse_pos("1-".to_string());
-        assert!(res.is_err());
-
         let res = parse_pos("1-1-1".to_string());
         assert!(res.is_err());
 
         let res = parse_pos("1-1-a".to_string());
         assert!(res.is_err());
 
-        // First number must be less than second
-        let res = parse_pos("1-1".to_string());
+        let res = parse_pos("--1".to_string());
         assert!(res.is_err());
-        assert_eq!(
-            res.unwrap_err().to_string(),
-            "First number in range (1) must be lower than second number (1)"
-        );
 
+        // First number must not be greater than second
         let res = parse_pos("2-1".to_string());
         assert!(res.is_err());
         assert_eq!(
             res.unwrap_err().to_string(),
-            "First number in range (2) must be lower than second number (1)"
+            "First number in range (2) \
+            must not be greater than second number (1)"
         );
 
         // All the following are acceptable
@@ -349,11 +555,37 @@ mod unit_tests {
         let res = parse_pos("15,19-20".to_string());
         assert!(res.is_ok());
         assert_eq!(res.unwrap(), vec![14..15, 18..20]);
+
+        let res = parse_pos("2-2".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![1..2]);
+
+        // Open-ended ranges start at the first position or run to the end
+        let res = parse_pos("-3".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..3]);
+
+        let res = parse_pos("3-".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![2..END]);
+
+        let res = parse_pos("1,-2,5-".to_string());
+        assert!(res.is_ok());
+        assert_eq!(res.unwrap(), vec![0..1, 0..2, 4..END]);
+    }
+
+    #[test]
+    fn test_complement() {
+        assert_eq!(complement(vec![0..1]), vec![1..END]);
+        assert_eq!(complement(vec![2..END]), vec![0..2]);
+        assert!(complement(vec![0..END]).is_empty());
+        assert_eq!(complement(vec![4..5, 0..1, 1..3]), vec![3..4, 5..END]);
+        assert_eq!(complement(vec![1..4, 2..3]), vec![0..1, 4..END]);
     }
 
     #[test]
     fn test_extract_fields() {
-        let rec = StringRecord::from(vec!["Captain", "Sham", "12345"]);
+        let rec = ["Captain", "Sham", "12345"];
         assert_eq!(extract_fields(&rec, &[0..1]), &["Captain"]);
         assert_eq!(extract_fields(&rec, &[1..2]), &["Sham"]);
         assert_eq!(
@@ -362,6 +594,7 @@ mod unit_tests {
         );
         assert_eq!(extract_fields(&rec, &[0..1, 3..4]), &["Captain"]);
         assert_eq!(extract_fields(&rec, &[1..2, 0..1]), &["Sham", "Captain"]);
+        assert_eq!(extract_fields(&rec, &[1..END]), &["Sham", "12345"]);
     }
 
     #[test]
@@ -375,6 +608,83 @@ mod unit_tests {
             extract_chars("ábc", &[0..1, 1..2, 4..5]),
             "áb".to_string()
         );
+        assert_eq!(extract_chars("ábc", &[1..END]), "bc".to_string());
+    }
+
+    #[test]
+    fn test_write_fields() {
+        let requote = Some(Quoting {
+            quote: b'"',
+            escape: None,
+        });
+        let write = |fields: &[&str], delimiter: &str| {
+            let fields: Vec<_> =
+                fields.iter().map(|f| f.as_bytes()).collect();
+            let mut out = vec![];
+            write_fields(&mut out, &fields, delimiter.as_bytes(), requote)
+                .unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(write(&[], ","), "\n");
+        assert_eq!(write(&[""], ","), "\n");
+        assert_eq!(write(&["a", "b"], ","), "a,b\n");
+        assert_eq!(write(&["a", "b"], " :: "), "a :: b\n");
+        assert_eq!(write(&["a,b", "c"], ","), "\"a,b\",c\n");
+        assert_eq!(write(&["a,b", "c"], "\t"), "a,b\tc\n");
+        assert_eq!(write(&["a::b"], "::"), "\"a::b\"\n");
+        assert_eq!(write(&[r#"say "hi""#], ","), "\"say \"\"hi\"\"\"\n");
+
+        // Another quote, escaped with a backslash
+        let mut out = vec![];
+        let quoting = Quoting {
+            quote: b'\'',
+            escape: Some(b'\\'),
+        };
+        write_fields(&mut out, &[b"it's", b"a\"b"], b",", Some(quoting))
+            .unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "'it\\'s',a\"b\n");
+
+        // Or written as they are
+        let mut out = vec![];
+        write_fields(&mut out, &[b"a,b", b"c"], b",", None).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "a,b,c\n");
+
+        // Bytes that aren't UTF-8 are kept
+        let mut out = vec![];
+        write_fields(&mut out, &[b"caf\xe9", b"\r"], b"\xa4", requote)
+            .unwrap();
+        assert_eq!(out, b"caf\xe9\xa4\"\r\"\n");
+    }
+
+    #[test]
+    fn test_parse_byte() {
+        assert_eq!(parse_byte("quote", "'").unwrap(), b'\'');
+        let res = parse_byte("quote", "");
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            r#"--quote "" must be a single byte"#
+        );
+        let res = parse_byte("escape", "é");
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err().to_string(),
+            r#"--escape "é" must be a single byte"#
+        );
+    }
+
+    #[test]
+    fn test_select_fields() {
+        let pos = [1..2];
+        assert_eq!(select_fields(&["a", "b"], &pos, false), Some(vec!["b"]));
+        assert_eq!(select_fields(&["a", "b"], &pos, true), Some(vec!["b"]));
+        assert_eq!(select_fields(&["a"], &pos, false), Some(vec!["a"]));
+        assert_eq!(select_fields(&["a"], &pos, true), None);
+        assert_eq!(select_fields(&[""], &pos, false), Some(vec![""]));
+        assert_eq!(
+            select_fields(&[b"a\xe9", b"b\r"], &pos, false),
+            Some(vec![b"b\r"])
+        );
     }
 
     #[test]
@@ -385,5 +695,6 @@ mod unit_tests {
         assert_eq!(extract_bytes("ábc", &[0..4]), "ábc".to_string());
         assert_eq!(extract_bytes("ábc", &[3..4, 2..3]), "cb".to_string());
         assert_eq!(extract_bytes("ábc", &[0..2, 5..6]), "á".to_string());
+        assert_eq!(extract_bytes("ábc", &[2..END]), "bc".to_string());
     }
 }
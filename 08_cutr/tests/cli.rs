@@ -9,6 +9,11 @@ const PRG: &str = "cutr";
 const CSV: &str = "tests/inputs/movies1.csv";
 const TSV: &str = "tests/inputs/movies1.tsv";
 const BOOKS: &str = "tests/inputs/books.tsv";
+const MIXED: &str = "tests/inputs/mixed.tsv";
+const MOVIES2: &str = "tests/inputs/movies2.csv";
+const QUOTES: &str = "tests/inputs/quotes.csv";
+const CRLF: &str = "tests/inputs/crlf.csv";
+const LATIN1: &str = "tests/inputs/latin1.csv";
 
 // --------------------------------------------------
 fn random_string() -> String {
@@ -33,11 +38,13 @@ fn gen_bad_file() -> String {
 #[test]
 fn skips_bad_file() -> Result<()> {
     let bad = gen_bad_file();
-    let expected = format!("{bad}: .* [(]os error 2[)]");
+    let expected = format!("{PRG}: {bad}: .* [(]os error 2[)]");
     Command::cargo_bin(PRG)?
         .args(["-f", "1", CSV, &bad, TSV])
         .assert()
-        .success()
+        .failure()
+        .code(1)
+        .stdout(predicate::str::ends_with("Les Misérables\n"))
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -150,6 +157,63 @@ fn dies_chars_bytes() -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn dies_only_delimited_bytes() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([CSV, "-s", "-b", "1"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_output_delimiter_chars() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([CSV, "--output-delimiter", ":", "-c", "1"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_raw_quote() -> Result<()> {
+    dies(
+        &[CSV, "-f", "1", "--mode", "raw", "--quote", "'"],
+        "--quote, --escape and --no-requote need --mode csv",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_quote() -> Result<()> {
+    dies(
+        &[CSV, "-f", "1", "--quote", "''"],
+        r#"--quote "''" must be a single byte"#,
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_escape() -> Result<()> {
+    dies(
+        &[CSV, "-f", "1", "--escape", ""],
+        r#"--escape "" must be a single byte"#,
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_mode() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([CSV, "-f", "1", "--mode", "tsv"])
+        .assert()
+        .failure();
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> Result<()> {
     let expected = fs::read_to_string(expected_file)?;
@@ -173,6 +237,15 @@ fn run_lossy(args: &[&str], expected_file: &str) -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+fn run_bytes(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read(expected_file)?;
+    let output = Command::cargo_bin(PRG)?.args(args).output().expect("fail");
+    assert!(output.status.success());
+    assert_eq!(output.stdout, expected);
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 fn tsv_f1() -> Result<()> {
@@ -340,3 +413,225 @@ fn tsv_c1_8() -> Result<()> {
 fn repeated_value() -> Result<()> {
     run(&[BOOKS, "-c", "1,1"], "tests/expected/books.c1,1.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn tsv_f2_open() -> Result<()> {
+    run(&[TSV, "-f", "2-"], "tests/expected/movies1.tsv.f2-.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f_open_2() -> Result<()> {
+    run(&[TSV, "-f", "-2"], "tests/expected/movies1.tsv.f-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f2_2() -> Result<()> {
+    run(&[TSV, "-f", "2-2"], "tests/expected/movies1.tsv.f2-2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_f2_open() -> Result<()> {
+    run(
+        &[CSV, "-f", "2-", "-d", ","],
+        "tests/expected/movies1.csv.f2-.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_f_open_2() -> Result<()> {
+    run(
+        &[CSV, "-f", "-2", "-d", ","],
+        "tests/expected/movies1.csv.f-2.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b3_open() -> Result<()> {
+    run(&[TSV, "-b", "3-"], "tests/expected/movies1.tsv.b3-.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_b_open_3() -> Result<()> {
+    run(&[TSV, "-b", "-3"], "tests/expected/movies1.tsv.b-3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c3_open() -> Result<()> {
+    run(&[TSV, "-c", "3-"], "tests/expected/movies1.tsv.c3-.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c_open_3() -> Result<()> {
+    run(&[TSV, "-c", "-3"], "tests/expected/movies1.tsv.c-3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_f2_complement() -> Result<()> {
+    run(
+        &[TSV, "--complement", "-f", "2"],
+        "tests/expected/movies1.tsv.f2.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_f1_complement() -> Result<()> {
+    run(
+        &[CSV, "--complement", "-f", "1", "-d", ","],
+        "tests/expected/movies1.csv.f1.dcomma.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn tsv_c_complement() -> Result<()> {
+    run(
+        &[TSV, "--complement", "-c", "2-3,5-"],
+        "tests/expected/movies1.tsv.c2-3,5-.complement.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mixed_f2() -> Result<()> {
+    run(&[MIXED, "-f", "2"], "tests/expected/mixed.tsv.f2.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn mixed_f2_only_delimited() -> Result<()> {
+    run(
+        &[MIXED, "-f", "2", "-s"],
+        "tests/expected/mixed.tsv.f2.s.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mixed_f1_3() -> Result<()> {
+    run(&[MIXED, "-f", "1,3"], "tests/expected/mixed.tsv.f1,3.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn mixed_f1_3_only_delimited() -> Result<()> {
+    run(
+        &[MIXED, "-f", "1,3", "--only-delimited"],
+        "tests/expected/mixed.tsv.f1,3.s.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn mixed_f1_3_output_delimiter() -> Result<()> {
+    run(
+        &[MIXED, "-f", "1,3", "--output-delimiter", " :: "],
+        "tests/expected/mixed.tsv.f1,3.odcolons.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn csv_f2_open_output_delimiter() -> Result<()> {
+    run(
+        &[CSV, "-f", "2-", "-d", ",", "--output-delimiter", "\t"],
+        "tests/expected/movies1.csv.f2-.dcomma.odtab.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn movies2_f1_raw() -> Result<()> {
+    run(
+        &[MOVIES2, "-f", "1", "-d", ",", "--mode", "raw"],
+        "tests/expected/movies2.csv.f1.dcomma.raw.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn movies2_f2_open_raw() -> Result<()> {
+    run(
+        &[MOVIES2, "-f", "2-", "-d", ",", "--mode", "raw"],
+        "tests/expected/movies2.csv.f2-.dcomma.raw.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_f2_raw() -> Result<()> {
+    run_bytes(
+        &[CRLF, "-f", "2", "-d", ",", "--mode", "raw"],
+        "tests/expected/crlf.csv.f2.dcomma.raw.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn latin1_f2_raw() -> Result<()> {
+    run_bytes(
+        &[LATIN1, "-f", "2", "-d", ",", "--mode", "raw"],
+        "tests/expected/latin1.csv.f2.dcomma.raw.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn movies2_f1_csv() -> Result<()> {
+    run(
+        &[MOVIES2, "-f", "1", "-d", ",", "--mode", "csv"],
+        "tests/expected/movies2.csv.f1.dcomma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn movies2_f1_no_requote() -> Result<()> {
+    run(
+        &[MOVIES2, "-f", "1", "-d", ",", "--no-requote"],
+        "tests/expected/movies2.csv.f1.dcomma.norequote.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn quotes_f2_1() -> Result<()> {
+    run(
+        &[
+            QUOTES, "-f", "2,1", "-d", ",", "--quote", "'", "--escape", "\\",
+        ],
+        "tests/expected/quotes.csv.f2,1.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn quotes_f2_1_no_requote() -> Result<()> {
+    run(
+        &[
+            QUOTES,
+            "-f",
+            "2,1",
+            "-d",
+            ",",
+            "--quote",
+            "'",
+            "--escape",
+            "\\",
+            "--no-requote",
+            "--output-delimiter",
+            " | ",
+        ],
+        "tests/expected/quotes.csv.f2,1.norequote.out",
+    )
+}
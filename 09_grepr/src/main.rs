@@ -1,11 +1,8 @@
 use anyhow::{anyhow, Result};
 use clap::Parser;
+use cliutil::{open, Reporter};
 use regex::{Regex, RegexBuilder};
-use std::{
-    fs::{self, File},
-    io::{self, BufRead, BufReader},
-    mem,
-};
+use std::{fs, io::BufRead, mem};
 use walkdir::WalkDir;
 
 #[derive(Debug, Parser)]
@@ -39,14 +36,15 @@ struct Args {
 
 // --------------------------------------------------
 fn main() {
-    if let Err(e) = run(Args::parse()) {
-        eprintln!("{}", e);
-        std::process::exit(1);
+    let mut reporter = Reporter::new(env!("CARGO_BIN_NAME"));
+    if let Err(e) = run(Args::parse(), &mut reporter) {
+        reporter.report(e);
     }
+    std::process::exit(reporter.exit_code());
 }
 
 // --------------------------------------------------
-fn run(args: Args) -> Result<()> {
+fn run(args: Args, reporter: &mut Reporter) -> Result<()> {
     let pattern = RegexBuilder::new(&args.pattern)
         .case_insensitive(args.insensitive)
         .build()
@@ -64,11 +62,11 @@ fn run(args: Args) -> Result<()> {
 
     for entry in entries {
         match entry {
-            Err(e) => eprintln!("{e}"),
+            Err(e) => reporter.report(e),
             Ok(filename) => match open(&filename) {
-                Err(e) => eprintln!("{filename}: {e}"),
+                Err(e) => reporter.report_file(&filename, e),
                 Ok(file) => match find_lines(file, &pattern, args.invert) {
-                    Err(e) => eprintln!("{e}"),
+                    Err(e) => reporter.report_file(&filename, e),
                     Ok(matches) => {
                         if args.count {
                             print(&filename, &format!("{}\n", matches.len()));
@@ -86,14 +84,6 @@ fn run(args: Args) -> Result<()> {
     Ok(())
 }
 
-// --------------------------------------------------
-fn open(filename: &str) -> Result<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(File::open(filename)?))),
-    }
-}
-
 // --------------------------------------------------
 fn find_lines<T: BufRead>(
     mut file: T,
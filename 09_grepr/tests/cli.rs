@@ -53,10 +53,12 @@ fn dies_bad_pattern() -> Result<()> {
 #[test]
 fn warns_bad_file() -> Result<()> {
     let bad = gen_bad_file();
-    let expected = format!("{bad}: .* [(]os error 2[)]");
+    let expected = format!("{PRG}: {bad}: .* [(]os error 2[)]");
     Command::cargo_bin(PRG)?
         .args(["foo", &bad])
         .assert()
+        .failure()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -236,7 +238,11 @@ fn warns_dir_not_recursive() -> Result<()> {
     Command::cargo_bin(PRG)?
         .args(["fox", INPUTS_DIR, FOX])
         .assert()
-        .stderr(predicate::str::contains("tests/inputs is a directory"))
+        .failure()
+        .code(1)
+        .stderr(predicate::str::contains(format!(
+            "{PRG}: {INPUTS_DIR} is a directory"
+        )))
         .stdout(predicate::str::contains(stdout));
     Ok(())
 }
@@ -1,11 +1,8 @@
 use crate::Column::*;
 use anyhow::{anyhow, bail, Result};
 use clap::{ArgAction, Parser};
-use std::{
-    cmp::Ordering::*,
-    fs::File,
-    io::{self, BufRead, BufReader},
-};
+use cliutil::{open, Reporter};
+use std::{cmp::Ordering::*, io::BufRead};
 
 #[derive(Debug, Parser)]
 #[command(author, version, about)]
@@ -48,10 +45,11 @@ enum Column<'a> {
 
 // --------------------------------------------------
 fn main() {
+    let mut reporter = Reporter::new(env!("CARGO_BIN_NAME"));
     if let Err(e) = run(Args::parse()) {
-        eprintln!("{e}");
-        std::process::exit(1);
+        reporter.report(e);
     }
+    std::process::exit(reporter.exit_code());
 }
 
 // --------------------------------------------------
@@ -71,8 +69,14 @@ fn run(args: Args) -> Result<()> {
         }
     };
 
-    let mut lines1 = open(file1)?.lines().map_while(Result::ok).map(case);
-    let mut lines2 = open(file2)?.lines().map_while(Result::ok).map(case);
+    let mut lines1 = open(file1)
+        .map_err(|e| anyhow!("{file1}: {e}"))?
+        .lines()
+        .map(|line| line.map(case).map_err(|e| anyhow!("{file1}: {e}")));
+    let mut lines2 = open(file2)
+        .map_err(|e| anyhow!("{file2}: {e}"))?
+        .lines()
+        .map(|line| line.map(case).map_err(|e| anyhow!("{file2}: {e}")));
 
     let print = |col: Column| {
         let mut columns = vec![];
@@ -108,33 +112,33 @@ fn run(args: Args) -> Result<()> {
         }
     };
 
-    let mut line1 = lines1.next();
-    let mut line2 = lines2.next();
+    let mut line1 = lines1.next().transpose()?;
+    let mut line2 = lines2.next().transpose()?;
 
     while line1.is_some() || line2.is_some() {
         match (&line1, &line2) {
             (Some(val1), Some(val2)) => match val1.cmp(val2) {
                 Equal => {
                     print(Col3(val1));
-                    line1 = lines1.next();
-                    line2 = lines2.next();
+                    line1 = lines1.next().transpose()?;
+                    line2 = lines2.next().transpose()?;
                 }
                 Less => {
                     print(Col1(val1));
-                    line1 = lines1.next();
+                    line1 = lines1.next().transpose()?;
                 }
                 Greater => {
                     print(Col2(val2));
-                    line2 = lines2.next();
+                    line2 = lines2.next().transpose()?;
                 }
             },
             (Some(val1), None) => {
                 print(Col1(val1));
-                line1 = lines1.next();
+                line1 = lines1.next().transpose()?;
             }
             (None, Some(val2)) => {
                 print(Col2(val2));
-                line2 = lines2.next();
+                line2 = lines2.next().transpose()?;
             }
             _ => (),
         }
@@ -142,13 +146,3 @@ fn run(args: Args) -> Result<()> {
 
     Ok(())
 }
-
-// --------------------------------------------------
-fn open(filename: &str) -> Result<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin()))),
-        _ => Ok(Box::new(BufReader::new(
-            File::open(filename).map_err(|e| anyhow!("{filename}: {e}"))?,
-        ))),
-    }
-}
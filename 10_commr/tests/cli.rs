@@ -40,11 +40,12 @@ fn gen_bad_file() -> String {
 #[test]
 fn dies_bad_file1() -> Result<()> {
     let bad = gen_bad_file();
-    let expected = format!("{bad}: .* [(]os error 2[)]");
+    let expected = format!("{PRG}: {bad}: .* [(]os error 2[)]");
     Command::cargo_bin(PRG)?
         .args([&bad, FILE1])
         .assert()
         .failure()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -53,11 +54,25 @@ fn dies_bad_file1() -> Result<()> {
 #[test]
 fn dies_bad_file2() -> Result<()> {
     let bad = gen_bad_file();
-    let expected = format!("{bad}: .* [(]os error 2[)]");
+    let expected = format!("{PRG}: {bad}: .* [(]os error 2[)]");
     Command::cargo_bin(PRG)?
         .args([FILE1, &bad])
         .assert()
         .failure()
+        .code(1)
+        .stderr(predicate::str::is_match(expected)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_dir() -> Result<()> {
+    let expected = format!("{PRG}: tests/inputs: .* [(]os error 21[)]");
+    Command::cargo_bin(PRG)?
+        .args([FILE1, "tests/inputs"])
+        .assert()
+        .failure()
+        .code(1)
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
@@ -0,0 +1,93 @@
+//! Helpers shared by the command-line programs in this repository
+
+use anyhow::Result;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+// --------------------------------------------------
+/// Open a file for buffered reading, where "-" means STDIN
+pub fn open(filename: impl AsRef<Path>) -> Result<Box<dyn BufRead>> {
+    let path = filename.as_ref();
+    if path == Path::new("-") {
+        Ok(Box::new(BufReader::new(io::stdin())))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(path)?)))
+    }
+}
+
+// --------------------------------------------------
+/// Prints errors to STDERR prefixed with the program name and remembers
+/// whether any were reported so the program can exit with a failure.
+#[derive(Debug)]
+pub struct Reporter {
+    program: String,
+    failed: bool,
+}
+
+impl Reporter {
+    pub fn new(program: &str) -> Self {
+        Reporter {
+            program: program.to_string(),
+            failed: false,
+        }
+    }
+
+    /// Report an error and mark the run as failed
+    pub fn report(&mut self, err: impl Display) {
+        eprintln!("{}: {err}", self.program);
+        self.failed = true;
+    }
+
+    /// Report an error about the named file
+    pub fn report_file(&mut self, filename: &str, err: impl Display) {
+        self.report(format_args!("{filename}: {err}"));
+    }
+
+    /// Mark the run as failed when the error was already shown elsewhere
+    pub fn fail(&mut self) {
+        self.failed = true;
+    }
+
+    /// Whether any error has been reported
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
+    /// The process exit code: 1 if any error was reported, 0 otherwise
+    pub fn exit_code(&self) -> i32 {
+        i32::from(self.failed)
+    }
+}
+
+// --------------------------------------------------
+#[cfg(test)]
+mod tests {
+    use super::{open, Reporter};
+
+    #[test]
+    fn test_open() {
+        assert!(open("Cargo.toml").is_ok());
+        assert!(open("no-such-file").is_err());
+    }
+
+    #[test]
+    fn test_reporter() {
+        let mut reporter = Reporter::new("test");
+        assert!(!reporter.failed());
+        assert_eq!(reporter.exit_code(), 0);
+
+        reporter.report_file("foo", "No such file or directory");
+        assert!(reporter.failed());
+        assert_eq!(reporter.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_reporter_fail() {
+        let mut reporter = Reporter::new("test");
+        reporter.fail();
+        assert!(reporter.failed());
+        assert_eq!(reporter.exit_code(), 1);
+    }
+}